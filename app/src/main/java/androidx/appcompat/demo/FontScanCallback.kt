@@ -0,0 +1,12 @@
+package androidx.appcompat.demo
+
+/**
+ * 流式字体扫描回调，由 native 层在扫描过程中调用
+ */
+interface FontScanCallback {
+    /** 每发现一个字体文件调用一次，参数为该文件信息的 JSON */
+    fun onFile(json: String)
+
+    /** 扫描结束时调用，参数为统计信息的 JSON */
+    fun onComplete(statsJson: String)
+}
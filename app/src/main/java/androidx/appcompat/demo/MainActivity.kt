@@ -108,8 +108,20 @@ class MainActivity : ComponentActivity() {
 
     // 声明native方法  
     external fun loadFontsInfo(directory: String): String
+    external fun loadFontsInfoWithOptions(directory: String, maxListed: Int, asciiHeaders: Boolean, crlfLineEndings: Boolean): String
     external fun copyFontFiles(sourceDirectory: String, targetDirectory: String, overwriteExisting: Boolean): String
+    external fun copyFontsJson(sourceDirectory: String, targetDirectory: String, overwriteExisting: Boolean): String
     external fun parseFontsDirectory(directory: String): String
+    external fun parseFontBytes(data: ByteArray): String
+    external fun scanFontsWithCallback(directory: String, callback: FontScanCallback)
+    external fun getFontNameRecord(fontPath: String, nameId: Int): String
+    external fun getGlyphName(fontPath: String, codepoint: Int): String
+    external fun renderFontSample(fontPath: String, text: String, px: Int): ByteArray
+    external fun largestFiles(directory: String, n: Int): String
+    external fun fontPathList(directory: String): String
+    external fun setLogTag(tag: String): Boolean
+    external fun directoryHasFonts(directory: String): Boolean
+    external fun fontFamiliesJson(directory: String): String
 
     // 文件夹选择器回调
     private var onSourceFolderSelected: ((String) -> Unit)? = null
@@ -0,0 +1,64 @@
+//! 统一的错误类型
+
+use std::path::PathBuf;
+
+use crate::font_parser::FontFormat;
+
+/// 库中扫描、字体解析和复制操作的错误
+///
+/// 复制操作不会中途返回错误，而是把这些错误的描述记录在 `CopyResult::errors` 中。
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("文件读写失败: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("解析字体数据失败: {0}")]
+    FaceParsing(#[from] ttf_parser::FaceParsingError),
+
+    #[error("解析字体数据失败: {0}")]
+    InvalidFont(#[from] ab_glyph::InvalidFont),
+
+    #[error("无法识别的字体格式")]
+    UnknownFormat,
+
+    #[error("暂不支持解析 {} 格式", .0.name())]
+    UnsupportedFormat(FontFormat),
+
+    #[error("{0}")]
+    Woff2(String),
+
+    #[error("读取文件不完整: 已读取 {read} 字节，预期 {expected} 字节")]
+    ReadFailed { read: u64, expected: u64 },
+
+    #[error("字体文件不完整（可能仍在写入）: 表数据需要 {expected} 字节，文件只有 {actual} 字节")]
+    Truncated { expected: u64, actual: u64 },
+
+    #[error("缺少 {0}")]
+    MissingData(&'static str),
+
+    #[error("无效参数: {0}")]
+    InvalidArgument(String),
+
+    #[error("无法读取目录 {path:?}: {source}")]
+    ReadDir {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("源目录无效: {0:?}")]
+    InvalidSourceDir(PathBuf),
+
+    #[error("无法创建目标目录: {0}")]
+    CreateTargetDir(#[source] std::io::Error),
+
+    #[error("目标目录不可写: {0}")]
+    TargetNotWritable(#[source] std::io::Error),
+
+    #[error("无法创建 ZIP 文件: {0}")]
+    CreateArchive(#[source] std::io::Error),
+
+    #[error("写入 ZIP 文件失败: {0}")]
+    Zip(#[from] zip::result::ZipError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
@@ -1,10 +1,20 @@
 use log::{error, info};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
 use std::fs;
-use std::path::Path;
-use std::time::SystemTime;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Instant, SystemTime};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
 
-use crate::scanner::{format_file_size, DirectoryScanner, FileInfo};
+use crate::error::Error;
+use crate::font_parser::FontParser;
+use crate::scanner::{
+    content_hash, format_file_size, same_contents, DirectoryScanner, FileInfo, FormatOptions,
+};
 
 /// 简化的复制结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,10 +24,28 @@ pub struct CopyResult {
     pub total_files: usize,
     pub successful_copies: usize,
     pub failed_copies: usize,
+    /// 按复制选项有意跳过的文件数，不计入 `failed_copies`
+    #[serde(default)]
+    pub skipped_copies: usize,
     pub total_size: u64,
     pub duration_ms: u64,
     pub details: Vec<CopyDetail>,
     pub errors: Vec<String>,
+    /// 因内容重复而跳过的文件数
+    #[serde(default)]
+    pub files_deduplicated: usize,
+    /// 因内容重复而节省的字节数
+    #[serde(default)]
+    pub bytes_deduplicated: u64,
+    /// 打包到 ZIP 时归档文件的大小（压缩后），复制到目录时为 `None`
+    #[serde(default)]
+    pub archive_size: Option<u64>,
+}
+
+/// 按复制选项预先确定的跳过列表，值为保留下来的文件名
+struct PlannedSkips {
+    superseded: HashMap<PathBuf, String>,
+    duplicates: HashMap<PathBuf, String>,
 }
 
 /// 复制详情
@@ -27,16 +55,52 @@ pub struct CopyDetail {
     pub file_size: u64,
     pub success: bool,
     pub error: Option<String>,
+    /// 复制耗时（毫秒），未执行复制时为 0
+    pub duration_ms: u64,
+    /// 按复制选项有意跳过（内容重复、旧版本、禁止嵌入或目标不比源文件旧），不计为失败
+    #[serde(default)]
+    pub skipped: bool,
+}
+
+/// 目标位置已存在同名文件时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictPolicy {
+    /// 保留目标文件，记为失败
+    #[default]
+    Skip,
+    /// 总是覆盖目标文件
+    Overwrite,
+    /// 仅当源文件修改时间严格更新时才覆盖，否则记为跳过；打包 ZIP 时等同于 `Overwrite`
+    OverwriteIfNewer,
 }
 
 /// 字体文件复制器
 pub struct FontCopier {
-    pub overwrite: bool,
+    pub conflict_policy: ConflictPolicy,
+    /// 拒绝复制 fsType 为“受限许可”的字体
+    pub respect_embedding_flags: bool,
+    /// 同一 PostScript 名称的字体只复制版本最新的一个
+    pub newest_version_only: bool,
+    /// 内容完全相同的字体只复制一份
+    pub deduplicate: bool,
+    /// 复制时新建目录的权限（如 `0o755`），已存在的目录不受影响；仅 Unix，其他平台忽略
+    pub dir_mode: Option<u32>,
 }
 
 impl FontCopier {
+    /// `overwrite` 为 true 时使用 `ConflictPolicy::Overwrite`，否则使用 `ConflictPolicy::Skip`
     pub fn new(overwrite: bool) -> Self {
-        Self { overwrite }
+        Self {
+            conflict_policy: if overwrite {
+                ConflictPolicy::Overwrite
+            } else {
+                ConflictPolicy::Skip
+            },
+            respect_embedding_flags: false,
+            newest_version_only: false,
+            deduplicate: false,
+            dir_mode: None,
+        }
     }
 
     /// 复制字体文件
@@ -45,48 +109,132 @@ impl FontCopier {
         let source_path = source_dir.as_ref();
         let target_path = target_dir.as_ref();
 
-        info!("开始复制字体文件: {:?} -> {:?}", source_path, target_path);
+        info!(source:? = source_path, target:? = target_path; "开始复制字体文件");
 
-        let mut result = CopyResult {
-            source_dir: source_path.display().to_string(),
-            target_dir: target_path.display().to_string(),
-            total_files: 0,
-            successful_copies: 0,
-            failed_copies: 0,
-            total_size: 0,
-            duration_ms: 0,
-            details: Vec::new(),
-            errors: Vec::new(),
-        };
+        let mut result = Self::empty_result(source_path, target_path);
 
         // 验证源目录
         if !source_path.exists() || !source_path.is_dir() {
-            result.errors.push(format!("源目录无效: {:?}", source_path));
+            result
+                .errors
+                .push(Error::InvalidSourceDir(source_path.to_path_buf()).to_string());
             return result;
         }
 
         // 创建目标目录
-        if let Err(e) = fs::create_dir_all(target_path) {
-            result.errors.push(format!("无法创建目标目录: {}", e));
+        if let Err(e) = self.create_target_dir(target_path) {
+            result.errors.push(Error::CreateTargetDir(e).to_string());
+            return result;
+        }
+
+        // 提前确认目标目录可写，避免逐个文件复制失败
+        if let Err(e) = Self::probe_writable(target_path) {
+            result.errors.push(Error::TargetNotWritable(e).to_string());
             return result;
         }
 
         // 扫描字体文件
         let font_files = DirectoryScanner::scan_fonts(source_path);
         result.total_files = font_files.len();
+        let skips = self.plan_skips(&font_files);
 
         // 复制每个文件
         for file_info in font_files {
-            let copy_detail = self.copy_single_file(&file_info, target_path);
+            let copy_detail = match self.skipped_detail(&file_info, &skips, &mut result) {
+                Some(detail) => detail,
+                None => self.copy_single_file(&file_info, target_path),
+            };
+            Self::record_detail(&mut result, copy_detail);
+        }
 
-            if copy_detail.success {
-                result.successful_copies += 1;
-                result.total_size += copy_detail.file_size;
-            } else {
-                result.failed_copies += 1;
+        result.duration_ms = start_time
+            .elapsed()
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        info!(
+            successful = result.successful_copies,
+            failed = result.failed_copies,
+            duration_ms = result.duration_ms;
+            "复制完成"
+        );
+        result
+    }
+
+    /// 将源目录中的字体打包为 ZIP 文件，用于导出字体包
+    ///
+    /// 所有字体都放在归档根目录下，同名文件依次命名为 `name (2).ttf`、`name (3).ttf`。
+    /// 冲突策略为 `ConflictPolicy::Skip` 且 ZIP 文件已存在时不会写入。
+    pub fn copy_to_zip<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        source_dir: P,
+        zip_path: Q,
+    ) -> CopyResult {
+        let start_time = SystemTime::now();
+        let source_path = source_dir.as_ref();
+        let zip_path = zip_path.as_ref();
+
+        info!(source:? = source_path, target:? = zip_path; "开始打包字体文件");
+
+        let mut result = Self::empty_result(source_path, zip_path);
+
+        // 验证源目录
+        if !source_path.is_dir() {
+            result
+                .errors
+                .push(Error::InvalidSourceDir(source_path.to_path_buf()).to_string());
+            return result;
+        }
+
+        let zip_file = match self.conflict_policy {
+            ConflictPolicy::Skip => fs::File::create_new(zip_path),
+            ConflictPolicy::Overwrite | ConflictPolicy::OverwriteIfNewer => {
+                fs::File::create(zip_path)
+            }
+        };
+        let mut zip = match zip_file {
+            Ok(file) => ZipWriter::new(file),
+            Err(e) => {
+                result.errors.push(Error::CreateArchive(e).to_string());
+                return result;
             }
+        };
+        let options =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
 
-            result.details.push(copy_detail);
+        let font_files = DirectoryScanner::scan_fonts(source_path);
+        result.total_files = font_files.len();
+        let skips = self.plan_skips(&font_files);
+
+        let mut used_names = HashSet::new();
+        for file_info in font_files {
+            let copy_detail = match self.skipped_detail(&file_info, &skips, &mut result) {
+                Some(detail) => detail,
+                None => {
+                    let entry_name = Self::unique_entry_name(&file_info.name, &mut used_names);
+                    let copy_start = Instant::now();
+                    let outcome =
+                        Self::write_zip_entry(&mut zip, &file_info.path, &entry_name, options);
+                    let duration_ms = copy_start.elapsed().as_millis() as u64;
+                    if let Err(e) = &outcome {
+                        error!(path:? = file_info.path; "打包失败 {}: {}", file_info.name, e);
+                    }
+                    CopyDetail {
+                        file_name: file_info.name.clone(),
+                        file_size: file_info.size,
+                        success: outcome.is_ok(),
+                        error: outcome.err().map(|e| e.to_string()),
+                        duration_ms,
+                        skipped: false,
+                    }
+                }
+            };
+            Self::record_detail(&mut result, copy_detail);
+        }
+
+        match zip.finish() {
+            Ok(file) => result.archive_size = file.metadata().ok().map(|m| m.len()),
+            Err(e) => result.errors.push(Error::Zip(e).to_string()),
         }
 
         result.duration_ms = start_time
@@ -95,28 +243,295 @@ impl FontCopier {
             .unwrap_or(0);
 
         info!(
-            "复制完成: 成功 {}, 失败 {}",
-            result.successful_copies, result.failed_copies
+            successful = result.successful_copies,
+            failed = result.failed_copies,
+            archive_size = result.archive_size;
+            "打包完成"
         );
         result
     }
 
+    /// 创建尚未开始复制的结果
+    fn empty_result(source_path: &Path, target_path: &Path) -> CopyResult {
+        CopyResult {
+            source_dir: source_path.display().to_string(),
+            target_dir: target_path.display().to_string(),
+            total_files: 0,
+            successful_copies: 0,
+            failed_copies: 0,
+            skipped_copies: 0,
+            total_size: 0,
+            duration_ms: 0,
+            details: Vec::new(),
+            errors: Vec::new(),
+            files_deduplicated: 0,
+            bytes_deduplicated: 0,
+            archive_size: None,
+        }
+    }
+
+    /// 按选项预先找出需要跳过的旧版本和重复文件
+    fn plan_skips(&self, font_files: &[FileInfo]) -> PlannedSkips {
+        PlannedSkips {
+            superseded: if self.newest_version_only {
+                Self::find_superseded_versions(font_files)
+            } else {
+                HashMap::new()
+            },
+            duplicates: if self.deduplicate {
+                Self::find_duplicate_contents(font_files)
+            } else {
+                HashMap::new()
+            },
+        }
+    }
+
+    /// 文件因选项被跳过时返回对应的详情，需要复制时返回 `None`
+    fn skipped_detail(
+        &self,
+        file_info: &FileInfo,
+        skips: &PlannedSkips,
+        result: &mut CopyResult,
+    ) -> Option<CopyDetail> {
+        let error = if let Some(newer) = skips.superseded.get(&file_info.path) {
+            format!("已跳过旧版本，保留较新的 {}", newer)
+        } else if let Some(original) = skips.duplicates.get(&file_info.path) {
+            result.files_deduplicated += 1;
+            result.bytes_deduplicated += file_info.size;
+            format!("与 {} 内容相同，已跳过", original)
+        } else if self.respect_embedding_flags
+            // 无法解析的文件不做限制
+            && FontParser::is_embedding_restricted(&file_info.path).unwrap_or(false)
+        {
+            "字体禁止嵌入/安装".to_string()
+        } else {
+            return None;
+        };
+
+        Some(CopyDetail {
+            file_name: file_info.name.clone(),
+            file_size: file_info.size,
+            success: false,
+            error: Some(error),
+            duration_ms: 0,
+            skipped: true,
+        })
+    }
+
+    /// 将单个文件的详情计入统计
+    fn record_detail(result: &mut CopyResult, detail: CopyDetail) {
+        if detail.success {
+            result.successful_copies += 1;
+            result.total_size += detail.file_size;
+        } else if detail.skipped {
+            result.skipped_copies += 1;
+        } else {
+            result.failed_copies += 1;
+        }
+        result.details.push(detail);
+    }
+
+    /// 为归档条目生成不重复的名称
+    fn unique_entry_name(name: &str, used_names: &mut HashSet<String>) -> String {
+        if used_names.insert(name.to_string()) {
+            return name.to_string();
+        }
+
+        let path = Path::new(name);
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy())
+            .unwrap_or_default();
+        let extension = path
+            .extension()
+            .map(|ext| format!(".{}", ext.to_string_lossy()))
+            .unwrap_or_default();
+        (2..)
+            .map(|n| format!("{} ({}){}", stem, n, extension))
+            .find(|candidate| used_names.insert(candidate.clone()))
+            .unwrap()
+    }
+
+    /// 将文件内容写入归档中的一个条目
+    fn write_zip_entry(
+        zip: &mut ZipWriter<fs::File>,
+        path: &Path,
+        entry_name: &str,
+        options: SimpleFileOptions,
+    ) -> zip::result::ZipResult<()> {
+        let mut source = fs::File::open(path)?;
+        zip.start_file(entry_name, options)?;
+        io::copy(&mut source, zip)?;
+        Ok(())
+    }
+
+    /// 创建目标目录及缺失的上级目录，并按 `dir_mode` 设置新建目录的权限
+    ///
+    /// 设置权限是尽力而为的：失败只记录警告，不影响复制。
+    fn create_target_dir(&self, target_dir: &Path) -> io::Result<()> {
+        // 记录将要新建的目录，从最深一级开始
+        #[cfg(unix)]
+        let created: Vec<PathBuf> = target_dir
+            .ancestors()
+            .take_while(|dir| !dir.as_os_str().is_empty() && !dir.exists())
+            .map(Path::to_path_buf)
+            .collect();
+
+        fs::create_dir_all(target_dir)?;
+
+        #[cfg(unix)]
+        if let Some(mode) = self.dir_mode {
+            use std::os::unix::fs::PermissionsExt;
+            for dir in created.iter().rev() {
+                if let Err(e) = fs::set_permissions(dir, fs::Permissions::from_mode(mode)) {
+                    log::warn!(path:? = dir, error_kind:? = e.kind(); "设置目录权限失败: {}", e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 在目标目录中创建并删除一个临时文件，检查目录是否可写
+    ///
+    /// 探测文件名包含进程号和进程内递增的序号，同一进程并发复制到同一目录时不会冲突。
+    fn probe_writable(target_dir: &Path) -> std::io::Result<()> {
+        let probe_path = Self::probe_path(target_dir);
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&probe_path)?;
+        fs::remove_file(&probe_path)
+    }
+
+    /// 生成本次探测使用的文件路径
+    fn probe_path(target_dir: &Path) -> PathBuf {
+        static PROBE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+        target_dir.join(format!(
+            ".write_probe_{}_{}",
+            std::process::id(),
+            PROBE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ))
+    }
+
+    /// 按 PostScript 名称分组，找出每组中被更新版本取代的文件
+    ///
+    /// 返回旧版本文件路径到最新版本文件名的映射。无法解析的文件不参与分组。
+    fn find_superseded_versions(font_files: &[FileInfo]) -> HashMap<PathBuf, String> {
+        let mut groups: HashMap<String, Vec<(&FileInfo, f64)>> = HashMap::new();
+        for file_info in font_files {
+            if let Ok((postscript_name, version)) = FontParser::read_version_info(&file_info.path) {
+                groups
+                    .entry(postscript_name)
+                    .or_default()
+                    .push((file_info, version));
+            }
+        }
+
+        let mut superseded = HashMap::new();
+        for members in groups.values() {
+            let Some(&(newest, _)) = members.iter().max_by(|a, b| a.1.total_cmp(&b.1)) else {
+                continue;
+            };
+
+            for (file_info, _) in members {
+                if file_info.path != newest.path {
+                    superseded.insert(file_info.path.clone(), newest.name.clone());
+                }
+            }
+        }
+        superseded
+    }
+
+    /// 找出内容与其他文件完全相同的文件
+    ///
+    /// 返回重复文件路径到保留文件名的映射，每组保留路径最小的一个。
+    /// 只对大小相同的文件计算哈希，哈希相同的文件再逐字节比较，无法读取的文件不参与比较。
+    fn find_duplicate_contents(font_files: &[FileInfo]) -> HashMap<PathBuf, String> {
+        let mut by_size: HashMap<u64, Vec<&FileInfo>> = HashMap::new();
+        for file_info in font_files {
+            by_size.entry(file_info.size).or_default().push(file_info);
+        }
+
+        let mut groups: HashMap<(u64, u64), Vec<&FileInfo>> = HashMap::new();
+        for (size, members) in by_size {
+            if members.len() < 2 {
+                continue;
+            }
+            for file_info in members {
+                if let Some(hash) = content_hash(&file_info.path) {
+                    groups.entry((size, hash)).or_default().push(file_info);
+                }
+            }
+        }
+
+        let mut duplicates = HashMap::new();
+        for mut members in groups.into_values() {
+            members.sort_by(|a, b| a.path.cmp(&b.path));
+            // 哈希可能碰撞，每个文件只归入内容逐字节相同的保留文件
+            let mut originals: Vec<&FileInfo> = Vec::new();
+            for file_info in members {
+                match originals
+                    .iter()
+                    .find(|original| same_contents(&original.path, &file_info.path))
+                {
+                    Some(original) => {
+                        duplicates.insert(file_info.path.clone(), original.name.clone());
+                    }
+                    None => originals.push(file_info),
+                }
+            }
+        }
+        duplicates
+    }
+
+    /// 源文件的修改时间是否严格晚于目标文件，无法读取修改时间时视为否
+    fn is_newer(source: &Path, target: &Path) -> bool {
+        let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+        match (modified(source), modified(target)) {
+            (Some(source), Some(target)) => source > target,
+            _ => false,
+        }
+    }
+
     /// 复制单个文件
     fn copy_single_file(&self, file_info: &FileInfo, target_dir: &Path) -> CopyDetail {
-        let target_path = target_dir.join(&file_info.name);
+        // 使用原始文件名，避免非 UTF-8 文件名经有损转换后无法对应
+        let target_path = match file_info.path.file_name() {
+            Some(file_name) => target_dir.join(file_name),
+            None => target_dir.join(&file_info.name),
+        };
 
         // 检查文件是否已存在
-        if target_path.exists() && !self.overwrite {
-            return CopyDetail {
-                file_name: file_info.name.clone(),
-                file_size: file_info.size,
-                success: false,
-                error: Some("文件已存在".to_string()),
+        if target_path.exists() {
+            // (原因, 是否为有意跳过)
+            let conflict = match self.conflict_policy {
+                ConflictPolicy::Skip => Some(("文件已存在", false)),
+                ConflictPolicy::Overwrite => None,
+                ConflictPolicy::OverwriteIfNewer
+                    if Self::is_newer(&file_info.path, &target_path) =>
+                {
+                    None
+                }
+                ConflictPolicy::OverwriteIfNewer => Some(("目标文件不比源文件旧，已跳过", true)),
             };
+            if let Some((error, skipped)) = conflict {
+                return CopyDetail {
+                    file_name: file_info.name.clone(),
+                    file_size: file_info.size,
+                    success: false,
+                    error: Some(error.to_string()),
+                    duration_ms: 0,
+                    skipped,
+                };
+            }
         }
 
         // 执行复制
-        match fs::copy(&file_info.path, &target_path) {
+        let copy_start = Instant::now();
+        let copy_result = fs::copy(&file_info.path, &target_path);
+        let duration_ms = copy_start.elapsed().as_millis() as u64;
+
+        match copy_result {
             Ok(_) => {
                 info!("成功复制: {}", file_info.name);
                 CopyDetail {
@@ -124,15 +539,23 @@ impl FontCopier {
                     file_size: file_info.size,
                     success: true,
                     error: None,
+                    duration_ms,
+                    skipped: false,
                 }
             }
             Err(e) => {
-                error!("复制失败 {}: {}", file_info.name, e);
+                error!(
+                    path:? = file_info.path,
+                    error_kind:? = e.kind();
+                    "复制失败 {}: {}", file_info.name, e
+                );
                 CopyDetail {
                     file_name: file_info.name.clone(),
                     file_size: file_info.size,
                     success: false,
                     error: Some(e.to_string()),
+                    duration_ms,
+                    skipped: false,
                 }
             }
         }
@@ -141,26 +564,48 @@ impl FontCopier {
 
 /// 格式化复制结果
 pub fn format_copy_result(result: &CopyResult) -> String {
+    format_copy_result_with_options(result, &FormatOptions::default())
+}
+
+/// 按输出选项格式化复制结果，可限制输出大小或转换换行符
+pub fn format_copy_result_with_options(result: &CopyResult, options: &FormatOptions) -> String {
     let mut output = String::new();
 
-    output.push_str(&format!("📁 字体文件复制\n"));
+    output.push_str("📁 字体文件复制\n");
     output.push_str(&format!("源目录: {}\n", result.source_dir));
     output.push_str(&format!("目标目录: {}\n", result.target_dir));
     output.push_str(&format!("耗时: {} ms\n\n", result.duration_ms));
 
-    output.push_str(&format!("📊 统计:\n"));
+    output.push_str("📊 统计:\n");
     output.push_str(&format!("• 发现: {} 个字体文件\n", result.total_files));
     output.push_str(&format!("• 成功: {} 个\n", result.successful_copies));
     output.push_str(&format!("• 失败: {} 个\n", result.failed_copies));
+    if result.skipped_copies > 0 {
+        output.push_str(&format!("• 跳过: {} 个\n", result.skipped_copies));
+    }
     output.push_str(&format!(
-        "• 总大小: {}\n\n",
+        "• 总大小: {}\n",
         format_file_size(result.total_size)
     ));
+    if result.files_deduplicated > 0 {
+        output.push_str(&format!(
+            "• 去重: {} 个，节省 {}\n",
+            result.files_deduplicated,
+            format_file_size(result.bytes_deduplicated)
+        ));
+    }
+    output.push('\n');
 
     if !result.details.is_empty() {
         output.push_str("📋 详情:\n");
         for detail in &result.details {
-            let icon = if detail.success { "✅" } else { "❌" };
+            let icon = if detail.success {
+                "✅"
+            } else if detail.skipped {
+                "ℹ️"
+            } else {
+                "❌"
+            };
             output.push_str(&format!(
                 "{} {} ({})",
                 icon,
@@ -175,6 +620,23 @@ pub fn format_copy_result(result: &CopyResult) -> String {
         }
     }
 
+    // 标记耗时最长的几个文件，便于排查慢速存储
+    let mut slowest: Vec<&CopyDetail> = result
+        .details
+        .iter()
+        .filter(|d| d.duration_ms > 0)
+        .collect();
+    slowest.sort_by_key(|d| std::cmp::Reverse(d.duration_ms));
+    if !slowest.is_empty() {
+        output.push_str("\n🐢 最慢:\n");
+        for detail in slowest.iter().take(3) {
+            output.push_str(&format!(
+                "• {} - {} ms\n",
+                detail.file_name, detail.duration_ms
+            ));
+        }
+    }
+
     if !result.errors.is_empty() {
         output.push_str("\n❌ 错误:\n");
         for error in &result.errors {
@@ -182,7 +644,101 @@ pub fn format_copy_result(result: &CopyResult) -> String {
         }
     }
 
-    output
+    options.apply(&output)
+}
+
+/// 找出目标目录中尚不存在的源字体文件，用于预览同步会新增哪些文件
+///
+/// `by_content` 为 false 时按文件名比较；为 true 时按文件内容比较，忽略文件名差异。
+pub fn fonts_missing_in_target(source: &str, target: &str, by_content: bool) -> Vec<FileInfo> {
+    let source_files = DirectoryScanner::scan_fonts(source);
+    let target_files = DirectoryScanner::scan_fonts(target);
+
+    if by_content {
+        let mut target_hashes: HashMap<(u64, u64), Vec<&Path>> = HashMap::new();
+        for f in &target_files {
+            if let Some(hash) = content_hash(&f.path) {
+                target_hashes
+                    .entry((f.size, hash))
+                    .or_default()
+                    .push(&f.path);
+            }
+        }
+        let target_sizes: HashSet<u64> = target_hashes.keys().map(|&(size, _)| size).collect();
+
+        // 大小不同的文件无需计算哈希，哈希相同时再逐字节比较以排除碰撞
+        source_files
+            .into_iter()
+            .filter(|f| {
+                !target_sizes.contains(&f.size)
+                    || content_hash(&f.path)
+                        .and_then(|hash| target_hashes.get(&(f.size, hash)))
+                        .is_none_or(|targets| {
+                            !targets.iter().any(|target| same_contents(&f.path, target))
+                        })
+            })
+            .collect()
+    } else {
+        let target_names: HashSet<&OsStr> = target_files
+            .iter()
+            .filter_map(|f| f.path.file_name())
+            .collect();
+        source_files
+            .into_iter()
+            .filter(|f| {
+                f.path
+                    .file_name()
+                    .is_none_or(|name| !target_names.contains(name))
+            })
+            .collect()
+    }
+}
+
+/// 两个目录树的比较结果，路径均为相对于各自根目录的路径，按路径排序
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TreeDiff {
+    pub only_in_a: Vec<PathBuf>,
+    pub only_in_b: Vec<PathBuf>,
+    /// 两边都存在但大小（或内容）不同
+    pub differing: Vec<PathBuf>,
+    pub identical: Vec<PathBuf>,
+}
+
+/// 按相对路径比较两个目录中的字体文件，用于复制后校验目标目录
+///
+/// 默认只比较大小；`by_content` 为 true 时还会比较大小相同文件的内容。
+pub fn compare_trees(a: &str, b: &str, by_content: bool) -> TreeDiff {
+    let relative = |root: &str, files: HashMap<PathBuf, FileInfo>| -> HashMap<PathBuf, FileInfo> {
+        files
+            .into_iter()
+            .filter_map(|(path, info)| Some((path.strip_prefix(root).ok()?.to_path_buf(), info)))
+            .collect()
+    };
+    let files_a = relative(a, DirectoryScanner::scan_map(a));
+    let mut files_b = relative(b, DirectoryScanner::scan_map(b));
+
+    let mut diff = TreeDiff::default();
+    for (path, info_a) in files_a {
+        let Some(info_b) = files_b.remove(&path) else {
+            diff.only_in_a.push(path);
+            continue;
+        };
+
+        let same = info_a.size == info_b.size
+            && (!by_content || same_contents(&info_a.path, &info_b.path));
+        if same {
+            diff.identical.push(path);
+        } else {
+            diff.differing.push(path);
+        }
+    }
+    diff.only_in_b = files_b.into_keys().collect();
+
+    diff.only_in_a.sort();
+    diff.only_in_b.sort();
+    diff.differing.sort();
+    diff.identical.sort();
+    diff
 }
 
 /// 主要的复制函数
@@ -191,3 +747,21 @@ pub fn copy_font_files(source_dir: &str, target_dir: &str, overwrite: bool) -> S
     let result = copier.copy_fonts(source_dir, target_dir);
     format_copy_result(&result)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_probe_writable_while_another_probe_exists() {
+        let target_dir = TempDir::new().unwrap();
+        // 模拟同一进程中另一个复制任务的探测文件尚未删除
+        let other_probe = FontCopier::probe_path(target_dir.path());
+        fs::write(&other_probe, b"").unwrap();
+
+        assert!(FontCopier::probe_writable(target_dir.path()).is_ok());
+        assert_ne!(FontCopier::probe_path(target_dir.path()), other_probe);
+        assert!(other_probe.exists());
+    }
+}
@@ -1,18 +1,90 @@
 use log::{info, warn};
 use serde::{Deserialize, Serialize};
 
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
+use std::io::Read;
 use std::path::Path;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::error::{Error, Result};
+use crate::scanner::{format_file_size, same_contents, FormatOptions};
+use crate::woff2;
+
+/// 串行化进程内对解析缓存文件的读写，避免并发的 JNI 调用互相覆盖或读到写了一半的缓存
+static CACHE_LOCK: Mutex<()> = Mutex::new(());
+
+/// 根据文件内容识别的字体格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FontFormat {
+    TrueType,
+    OpenTypeCff,
+    Woff,
+    Woff2,
+    Collection,
+}
+
+impl FontFormat {
+    /// 根据文件头的魔数识别格式
+    pub fn from_magic(data: &[u8]) -> Option<Self> {
+        match data.get(..4)? {
+            [0x00, 0x01, 0x00, 0x00] | b"true" => Some(FontFormat::TrueType),
+            b"OTTO" => Some(FontFormat::OpenTypeCff),
+            b"wOFF" => Some(FontFormat::Woff),
+            b"wOF2" => Some(FontFormat::Woff2),
+            b"ttcf" => Some(FontFormat::Collection),
+            _ => None,
+        }
+    }
+
+    /// 格式的显示名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            FontFormat::TrueType => "TrueType",
+            FontFormat::OpenTypeCff => "OpenType (CFF)",
+            FontFormat::Woff => "WOFF",
+            FontFormat::Woff2 => "WOFF2",
+            FontFormat::Collection => "TrueType Collection",
+        }
+    }
+}
 
 /// 字体映射信息结构体
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FontMapping {
     pub file_path: String,
+    pub detected_format: FontFormat,
     pub font_name: String,
     pub family_name: Option<String>,
     pub style_name: Option<String>,
+    pub version: Option<String>,
+    /// 由 `style_flags` 派生：粗体
     pub is_bold: bool,
+    /// 由 `style_flags` 派生：斜体或倾斜体
     pub is_italic: bool,
+    #[serde(default)]
+    pub style_flags: StyleFlags,
+    #[serde(default)]
+    pub postscript_name: Option<String>,
+    /// GSUB/GPOS 中的 OpenType 特性标签（如 `liga`、`smcp`），已排序去重
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub num_glyphs: u16,
+    /// 原始文件大小（字节）
+    #[serde(default)]
+    pub file_size: u64,
+    /// 是否包含轮廓（glyf、CFF 或 CFF2 表）；为 false 时为纯位图字体（如 CBDT/sbix 表情字体）
+    #[serde(default)]
+    pub has_outlines: bool,
+    /// name 表中出现的名称 ID，已排序去重，用于排查显示名称缺失的字体
+    #[serde(default)]
+    pub name_record_ids: Vec<u16>,
+    /// cmap 能显示的书写系统（如 `Latin`、`Han`），按名称排序
+    #[serde(default)]
+    pub scripts: Vec<String>,
 }
 
 /// 字体解析结果
@@ -25,12 +97,82 @@ pub struct FontParseResult {
     pub errors: Vec<String>,
 }
 
+/// 字体解析选项，用于跳过不需要的解析步骤
+#[derive(Debug, Clone, Copy)]
+pub struct FontParseOptions {
+    /// 读取 name 表中的名称和版本；为 false 时 `font_name` 和 `name_record_ids` 为空，其余名称字段为 `None`
+    pub extract_names: bool,
+    /// 读取 OS/2 表中的样式标志；为 false 时样式字段均为默认值
+    pub detect_style: bool,
+}
+
+impl Default for FontParseOptions {
+    fn default() -> Self {
+        Self {
+            extract_names: true,
+            detect_style: true,
+        }
+    }
+}
+
+/// 根据 OS/2 表 fsSelection、usWeightClass 和 usWidthClass 计算的样式标志
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StyleFlags {
+    /// fsSelection 的 BOLD 位，或字重不低于 600
+    pub bold: bool,
+    /// fsSelection 的 ITALIC 位（真斜体）
+    pub italic: bool,
+    /// fsSelection 的 OBLIQUE 位（OS/2 版本 4 起有效）
+    pub oblique: bool,
+    /// 字宽小于 Normal (5)
+    pub condensed: bool,
+    /// 字宽大于 Normal (5)
+    pub expanded: bool,
+    pub weight_class: u16,
+    pub width_class: u16,
+}
+
+/// 解析缓存条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedMapping {
+    /// 文件修改时间（Unix 纳秒）
+    mtime: u64,
+    mapping: FontMapping,
+}
+
+/// 字体预览位图（8 位灰度，按行存储）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleBitmap {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl SampleBitmap {
+    /// 打包为 宽度(u32 BE) + 高度(u32 BE) + 像素数据，便于通过 JNI 传递
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.pixels.len());
+        bytes.extend_from_slice(&self.width.to_be_bytes());
+        bytes.extend_from_slice(&self.height.to_be_bytes());
+        bytes.extend_from_slice(&self.pixels);
+        bytes
+    }
+}
+
 /// 字体解析器
 pub struct FontParser;
 
 impl FontParser {
     /// 解析指定目录中的所有字体文件
     pub fn parse_fonts_directory<P: AsRef<Path>>(directory: P) -> FontParseResult {
+        Self::parse_fonts_directory_with_options(directory, FontParseOptions::default())
+    }
+
+    /// 按指定选项解析目录中的所有字体文件，只需要格式和大小时可跳过名称和样式解析
+    pub fn parse_fonts_directory_with_options<P: AsRef<Path>>(
+        directory: P,
+        options: FontParseOptions,
+    ) -> FontParseResult {
         let mut result = FontParseResult {
             total_files: 0,
             successful_parses: 0,
@@ -39,7 +181,7 @@ impl FontParser {
             errors: Vec::new(),
         };
 
-        info!("开始解析字体目录: {:?}", directory.as_ref());
+        info!(path:? = directory.as_ref(); "开始解析字体目录");
 
         // 获取所有字体文件
         let font_files = Self::collect_font_files(directory.as_ref());
@@ -49,14 +191,86 @@ impl FontParser {
 
         // 解析每个字体文件
         for font_file in font_files {
+            match Self::parse_font_file_with_options(&font_file, options) {
+                Ok(mapping) => {
+                    result.mappings.push(mapping);
+                    result.successful_parses += 1;
+                }
+                Err(error) => {
+                    let error_msg = format!("解析文件 {} 失败: {}", font_file.display(), error);
+                    warn!(path:% = font_file.display(), error_kind = "parse"; "{}", error_msg);
+                    result.errors.push(error_msg);
+                    result.failed_parses += 1;
+                }
+            }
+        }
+
+        info!(
+            successful = result.successful_parses,
+            failed = result.failed_parses;
+            "字体解析完成"
+        );
+
+        result
+    }
+
+    /// 解析目录中的字体文件，并使用以路径和修改时间为键的缓存
+    ///
+    /// 只有新增或修改过的文件会被重新解析；已删除文件的缓存条目会被移除。
+    /// 解析失败的文件不会写入缓存，下次仍会重试。
+    /// 可在多个线程中并发调用，同一进程内的缓存读写会依次执行。
+    pub fn parse_fonts_cached<P: AsRef<Path>, Q: AsRef<Path>>(
+        directory: P,
+        cache_path: Q,
+    ) -> FontParseResult {
+        // 某次调用 panic 后缓存文件仍然完整，可以继续使用
+        let _guard = CACHE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let cache_path = cache_path.as_ref();
+        let mut cache = Self::load_cache(cache_path);
+        let mut fresh_cache = HashMap::new();
+
+        let mut result = FontParseResult {
+            total_files: 0,
+            successful_parses: 0,
+            failed_parses: 0,
+            mappings: Vec::new(),
+            errors: Vec::new(),
+        };
+
+        let font_files = Self::collect_font_files(directory.as_ref());
+        result.total_files = font_files.len();
+
+        let mut cache_hits = 0;
+        for font_file in font_files {
+            let key = font_file.to_string_lossy().to_string();
+            let mtime = Self::file_mtime(&font_file);
+
+            if let (Some(mtime), Some(cached)) = (mtime, cache.remove(&key)) {
+                if cached.mtime == mtime {
+                    result.mappings.push(cached.mapping.clone());
+                    result.successful_parses += 1;
+                    fresh_cache.insert(key, cached);
+                    cache_hits += 1;
+                    continue;
+                }
+            }
+
             match Self::parse_font_file(&font_file) {
                 Ok(mapping) => {
+                    if let Some(mtime) = mtime {
+                        let cached = CachedMapping {
+                            mtime,
+                            mapping: mapping.clone(),
+                        };
+                        fresh_cache.insert(key, cached);
+                    }
                     result.mappings.push(mapping);
                     result.successful_parses += 1;
                 }
                 Err(error) => {
                     let error_msg = format!("解析文件 {} 失败: {}", font_file.display(), error);
-                    warn!("{}", error_msg);
+                    warn!(path:% = font_file.display(), error_kind = "parse"; "{}", error_msg);
                     result.errors.push(error_msg);
                     result.failed_parses += 1;
                 }
@@ -64,13 +278,49 @@ impl FontParser {
         }
 
         info!(
-            "字体解析完成: 成功 {}, 失败 {}",
-            result.successful_parses, result.failed_parses
+            successful = result.successful_parses,
+            cache_hits = cache_hits,
+            failed = result.failed_parses;
+            "字体解析完成"
         );
 
+        Self::save_cache(cache_path, &fresh_cache);
         result
     }
 
+    /// 读取解析缓存，文件不存在或损坏时返回空缓存
+    fn load_cache(cache_path: &Path) -> HashMap<String, CachedMapping> {
+        fs::read(cache_path)
+            .ok()
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default()
+    }
+
+    /// 写入解析缓存，失败时只记录警告
+    fn save_cache(cache_path: &Path, cache: &HashMap<String, CachedMapping>) {
+        let data = match serde_json::to_vec(cache) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("序列化字体缓存失败: {}", e);
+                return;
+            }
+        };
+        // 先写入临时文件再重命名，其他进程不会读到不完整的缓存
+        let temp_path = cache_path.with_extension(format!("tmp{}", std::process::id()));
+        if let Err(e) = fs::write(&temp_path, data).and_then(|_| fs::rename(&temp_path, cache_path))
+        {
+            warn!("写入字体缓存失败 {:?}: {}", cache_path, e);
+            let _ = fs::remove_file(&temp_path);
+        }
+    }
+
+    /// 文件修改时间（Unix 纳秒）
+    fn file_mtime(path: &Path) -> Option<u64> {
+        let modified = fs::metadata(path).ok()?.modified().ok()?;
+        let since_epoch = modified.duration_since(SystemTime::UNIX_EPOCH).ok()?;
+        Some(since_epoch.as_nanos() as u64)
+    }
+
     /// 收集目录中的所有字体文件
     fn collect_font_files(directory: &Path) -> Vec<std::path::PathBuf> {
         let mut font_files = Vec::new();
@@ -92,7 +342,7 @@ impl FontParser {
         let entries = match fs::read_dir(directory) {
             Ok(entries) => entries,
             Err(e) => {
-                warn!("无法读取目录 {:?}: {}", directory, e);
+                warn!(path:? = directory, error_kind:? = e.kind(); "无法读取目录: {}", e);
                 return;
             }
         };
@@ -102,53 +352,451 @@ impl FontParser {
 
             if path.is_dir() {
                 Self::collect_font_files_recursive(&path, font_files, depth + 1);
-            } else if path.is_file() && Self::is_font_file(&path) {
+            } else if path.is_file()
+                && (Self::is_font_file(&path)
+                    || (!Self::has_font_like_extension(&path) && Self::has_font_header(&path)))
+            {
                 font_files.push(path);
             }
         }
     }
 
-    /// 检查是否为字体文件
+    /// 检查扩展名是否为可解析的字体扩展名
+    ///
+    /// 没有字体扩展名的文件在收集时还会按文件头识别，格式始终以文件内容为准。
     fn is_font_file(path: &Path) -> bool {
         if let Some(extension) = path.extension() {
             if let Some(ext_str) = extension.to_str() {
                 let ext_lower = ext_str.to_lowercase();
-                return matches!(ext_lower.as_str(), "ttf" | "otf" | "ttc" | "otc");
+                return matches!(ext_lower.as_str(), "ttf" | "otf" | "ttc" | "otc" | "woff2");
             }
         }
         false
     }
 
+    /// 扩展名表明是字体（包括暂不支持解析的 WOFF），这类文件不再按内容识别
+    fn has_font_like_extension(path: &Path) -> bool {
+        Self::is_font_file(path)
+            || path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("woff"))
+    }
+
+    /// 检查文件是否具有有效的字体文件头，只读取文件头和表目录
+    ///
+    /// SFNT 要求每条表记录的标签为可打印 ASCII 且按升序排列、数据位于表目录之后且不超出文件；
+    /// 集合文件要求版本和字体数量有效；WOFF2 要求头部记录的长度与文件大小一致。
+    /// 暂不支持的 WOFF 不会被识别。
+    fn has_font_header(path: &Path) -> bool {
+        let Ok(mut file) = fs::File::open(path) else {
+            return false;
+        };
+        let Ok(file_len) = file.metadata().map(|m| m.len()) else {
+            return false;
+        };
+        let mut header = [0u8; 12];
+        if file.read_exact(&mut header).is_err() {
+            return false;
+        }
+
+        let read_u16 = |data: &[u8], offset: usize| {
+            u16::from_be_bytes([data[offset], data[offset + 1]]) as u64
+        };
+        let read_u32 = |data: &[u8], offset: usize| {
+            u32::from_be_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ]) as u64
+        };
+
+        match FontFormat::from_magic(&header) {
+            Some(FontFormat::TrueType | FontFormat::OpenTypeCff) => {
+                let num_tables = read_u16(&header, 4);
+                let directory_end = 12 + num_tables * 16;
+                if num_tables == 0 || directory_end > file_len {
+                    return false;
+                }
+                let mut records = vec![0u8; num_tables as usize * 16];
+                if file.read_exact(&mut records).is_err() {
+                    return false;
+                }
+
+                let mut previous_tag: Option<&[u8]> = None;
+                records.chunks_exact(16).all(|record| {
+                    let tag = &record[..4];
+                    let valid = tag.iter().all(|b| (0x20..=0x7E).contains(b))
+                        && previous_tag.is_none_or(|previous| previous < tag)
+                        && read_u32(record, 8) >= directory_end
+                        && read_u32(record, 8) + read_u32(record, 12) <= file_len;
+                    previous_tag = Some(tag);
+                    valid
+                })
+            }
+            Some(FontFormat::Collection) => {
+                matches!(read_u32(&header, 4), 0x0001_0000 | 0x0002_0000)
+                    && read_u32(&header, 8) > 0
+            }
+            Some(FontFormat::Woff2) => read_u32(&header, 8) == file_len,
+            Some(FontFormat::Woff) | None => false,
+        }
+    }
+
     /// 解析单个字体文件
-    fn parse_font_file(font_path: &Path) -> Result<FontMapping, String> {
+    fn parse_font_file(font_path: &Path) -> Result<FontMapping> {
+        Self::parse_font_file_with_options(font_path, FontParseOptions::default())
+    }
+
+    /// 按指定选项解析单个字体文件
+    fn parse_font_file_with_options(
+        font_path: &Path,
+        options: FontParseOptions,
+    ) -> Result<FontMapping> {
         // 读取字体文件
-        let font_data = fs::read(font_path).map_err(|e| format!("读取文件失败: {}", e))?;
+        let font_data = Self::read_font_data(font_path)?;
+        let mut mapping = Self::parse_bytes_with_options(&font_data, options)?;
+        mapping.file_path = font_path.to_string_lossy().to_string();
+        Ok(mapping)
+    }
+
+    /// 直接从内存中的字体数据解析映射信息，`file_path` 为空
+    pub fn parse_bytes(font_data: &[u8]) -> Result<FontMapping> {
+        Self::parse_bytes_with_options(font_data, FontParseOptions::default())
+    }
+
+    /// 读取数据源中的全部字节后解析，`file_path` 为空
+    ///
+    /// 用于没有文件系统路径的数据，如经 JNI 传入的 Android asset `InputStream` 内容。
+    pub fn parse_reader<R: Read>(mut reader: R) -> Result<FontMapping> {
+        let mut font_data = Vec::new();
+        reader.read_to_end(&mut font_data)?;
+        Self::parse_bytes(&font_data)
+    }
 
-        // 解析字体数据
-        let face = ttf_parser::Face::parse(&font_data, 0)
-            .map_err(|e| format!("解析字体数据失败: {:?}", e))?;
+    /// 按指定选项从内存中的字体数据解析映射信息
+    fn parse_bytes_with_options(
+        font_data: &[u8],
+        options: FontParseOptions,
+    ) -> Result<FontMapping> {
+        let file_size = font_data.len() as u64;
+
+        let (detected_format, sfnt_data) = Self::load_sfnt(font_data)?;
+        let face = ttf_parser::Face::parse(&sfnt_data, 0)?;
 
         // 提取字体名称信息
-        let font_name = Self::extract_font_name(&face)?;
-        let family_name = Self::extract_family_name(&face);
-        let style_name = Self::extract_style_name(&face);
+        let (font_name, family_name, style_name, version, postscript_name, name_record_ids) =
+            if options.extract_names {
+                (
+                    Self::extract_font_name(&face)?,
+                    Self::extract_family_name(&face),
+                    Self::extract_style_name(&face),
+                    Self::extract_version(&face),
+                    Self::extract_postscript_name(&face),
+                    Self::name_record_ids(&face),
+                )
+            } else {
+                (String::new(), None, None, None, None, Vec::new())
+            };
 
         // 判断字体样式
-        let is_bold = Self::is_bold_font(&face);
-        let is_italic = Self::is_italic_font(&face);
+        let style_flags = if options.detect_style {
+            Self::extract_style_flags(&face)
+        } else {
+            StyleFlags::default()
+        };
 
         Ok(FontMapping {
-            file_path: font_path.to_string_lossy().to_string(),
+            file_path: String::new(),
+            detected_format,
             font_name,
             family_name,
             style_name,
-            is_bold,
-            is_italic,
+            version,
+            is_bold: style_flags.bold,
+            is_italic: style_flags.italic || style_flags.oblique,
+            style_flags,
+            postscript_name,
+            features: Self::feature_tags(&face),
+            // 字形数量来自 maxp 表，位图字体同样具备，不依赖 glyf/CFF
+            num_glyphs: face.number_of_glyphs(),
+            file_size,
+            has_outlines: Self::has_outlines(&face),
+            name_record_ids,
+            scripts: Self::supported_scripts(&face),
+        })
+    }
+
+    /// 根据文件内容（而不是扩展名）识别格式，并得到可交给 ttf_parser 解析的 SFNT 数据
+    ///
+    /// 集合文件原样返回（解析时只使用第一个字体），WOFF2 先在内存中解码为 SFNT。
+    fn load_sfnt(font_data: &[u8]) -> Result<(FontFormat, Cow<'_, [u8]>)> {
+        let format = FontFormat::from_magic(font_data).ok_or(Error::UnknownFormat)?;
+        let sfnt_data = match format {
+            FontFormat::TrueType | FontFormat::OpenTypeCff | FontFormat::Collection => {
+                Cow::Borrowed(font_data)
+            }
+            FontFormat::Woff2 => Cow::Owned(woff2::decode(font_data).map_err(Error::Woff2)?),
+            FontFormat::Woff => return Err(Error::UnsupportedFormat(format)),
+        };
+        Self::check_truncated(&sfnt_data, format)?;
+        Ok((format, sfnt_data))
+    }
+
+    /// 检查 SFNT 表目录及其列出的所有表是否都在数据范围内
+    ///
+    /// 正在下载或复制的文件可能只写入了一部分，此时返回 `Error::Truncated`，
+    /// 调用方可以稍后重试，而不是当作损坏的字体。集合文件只检查第一个字体。
+    fn check_truncated(font_data: &[u8], format: FontFormat) -> Result<()> {
+        // 偏移和长度来自文件内容，统一在 u64 中计算，避免 32 位平台上溢出后绕过检查
+        let actual = font_data.len() as u64;
+        let truncated = |expected: u64| Error::Truncated { expected, actual };
+        let read = |offset: u64, size: u64| {
+            let end = offset + size;
+            if end > actual {
+                return Err(truncated(end));
+            }
+            Ok(font_data[offset as usize..end as usize]
+                .iter()
+                .fold(0u64, |value, &byte| value << 8 | byte as u64))
+        };
+
+        let directory = if format == FontFormat::Collection {
+            read(12, 4)?
+        } else {
+            0
+        };
+        let num_tables = read(directory + 4, 2)?;
+
+        for index in 0..num_tables {
+            let record = directory + 12 + index * 16;
+            let end = read(record + 8, 4)? + read(record + 12, 4)?;
+            if end > actual {
+                return Err(truncated(end));
+            }
+        }
+        Ok(())
+    }
+
+    /// 完整读取字体文件，并校验读取的字节数与文件大小一致
+    fn read_font_data(font_path: &Path) -> Result<Vec<u8>> {
+        let mut file = fs::File::open(font_path)?;
+        let expected = file.metadata()?.len();
+        Self::read_full(&mut file, expected)
+    }
+
+    /// 读取直到 EOF，被中断的读取会重试
+    ///
+    /// 可移动存储上偶尔出现读取中断或提前结束，读取字节数与 `expected` 不符时返回错误。
+    fn read_full<R: Read>(reader: &mut R, expected: u64) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(expected as usize);
+        let mut buffer = [0u8; 64 * 1024];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(n) => data.extend_from_slice(&buffer[..n]),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        if data.len() as u64 != expected {
+            return Err(Error::ReadFailed {
+                read: data.len() as u64,
+                expected,
+            });
+        }
+        Ok(data)
+    }
+
+    /// 读取文件头识别字体格式
+    pub fn detect_format(font_path: &Path) -> Result<FontFormat> {
+        let mut header = [0u8; 4];
+        fs::File::open(font_path)?.read_exact(&mut header)?;
+
+        FontFormat::from_magic(&header).ok_or(Error::UnknownFormat)
+    }
+
+    /// 将 WOFF2 字体转换为普通 SFNT（TTF/OTF）文件
+    pub fn convert_woff2_to_ttf(src: &Path, dst: &Path) -> Result<()> {
+        let woff2_data = fs::read(src)?;
+        if FontFormat::from_magic(&woff2_data) != Some(FontFormat::Woff2) {
+            return Err(Error::InvalidArgument("源文件不是 WOFF2 格式".to_string()));
+        }
+
+        let sfnt_data = woff2::decode(&woff2_data).map_err(Error::Woff2)?;
+        fs::write(dst, &sfnt_data)?;
+
+        info!(
+            "WOFF2 转换完成: {} -> {} ({} -> {})",
+            src.display(),
+            dst.display(),
+            format_file_size(woff2_data.len() as u64),
+            format_file_size(sfnt_data.len() as u64)
+        );
+        Ok(())
+    }
+
+    /// 以指定像素大小渲染一段示例文本，用于生成字体缩略图
+    ///
+    /// 位图像素数超过 `MAX_SAMPLE_PIXELS` 时返回 `Error::InvalidArgument`，
+    /// 避免过大的字号或过长的文本分配巨大的缓冲区。
+    pub fn render_sample(font_path: &Path, text: &str, px: u32) -> Result<SampleBitmap> {
+        use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+
+        const MAX_SAMPLE_PIXELS: u64 = 4096 * 4096;
+
+        let font_data = Self::read_font_data(font_path)?;
+        let (_, sfnt_data) = Self::load_sfnt(&font_data)?;
+        let font = FontRef::try_from_slice(&sfnt_data)?;
+        let scaled = font.as_scaled(PxScale::from(px as f32));
+
+        // 沿基线排布字形
+        let mut glyphs = Vec::new();
+        let mut caret = 0.0f32;
+        let mut previous = None;
+        for ch in text.chars() {
+            let glyph_id = scaled.glyph_id(ch);
+            if let Some(previous) = previous {
+                caret += scaled.kern(previous, glyph_id);
+            }
+            glyphs.push(
+                glyph_id.with_scale_and_position(scaled.scale(), point(caret, scaled.ascent())),
+            );
+            caret += scaled.h_advance(glyph_id);
+            previous = Some(glyph_id);
+        }
+
+        // 浮点到整数的转换会饱和，像素数在 u64 中计算后再检查上限
+        let width = caret.ceil().max(0.0) as u32;
+        let height = (scaled.ascent() - scaled.descent()).ceil().max(0.0) as u32;
+        let pixel_count = width as u64 * height as u64;
+        if pixel_count > MAX_SAMPLE_PIXELS {
+            return Err(Error::InvalidArgument(format!(
+                "预览尺寸过大: {}x{}，最多 {} 像素",
+                width, height, MAX_SAMPLE_PIXELS
+            )));
+        }
+        let mut pixels = vec![0u8; pixel_count as usize];
+
+        for glyph in glyphs {
+            if let Some(outlined) = font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|x, y, coverage| {
+                    let x = x as i32 + bounds.min.x as i32;
+                    let y = y as i32 + bounds.min.y as i32;
+                    if x >= 0 && y >= 0 && (x as u32) < width && (y as u32) < height {
+                        let pixel = &mut pixels[y as usize * width as usize + x as usize];
+                        *pixel = pixel.saturating_add((coverage * 255.0) as u8);
+                    }
+                });
+            }
+        }
+
+        Ok(SampleBitmap {
+            width,
+            height,
+            pixels,
         })
     }
 
+    /// 读取指定 ID 的名称记录（如 14 为许可证 URL）
+    pub fn name_record(font_path: &Path, name_id: u16) -> Result<Option<String>> {
+        let font_data = Self::read_font_data(font_path)?;
+        let (_, sfnt_data) = Self::load_sfnt(&font_data)?;
+        let face = ttf_parser::Face::parse(&sfnt_data, 0)?;
+
+        Ok(face
+            .names()
+            .into_iter()
+            .filter(|name| name.name_id == name_id)
+            .find_map(|name| name.to_string()))
+    }
+
+    /// 查找字符对应的字形名称
+    ///
+    /// 字体中没有该字符，或缺少 post 表（无字形名称）时返回 `None`。
+    pub fn glyph_name(font_path: &Path, ch: char) -> Result<Option<String>> {
+        let font_data = Self::read_font_data(font_path)?;
+        let (_, sfnt_data) = Self::load_sfnt(&font_data)?;
+        let face = ttf_parser::Face::parse(&sfnt_data, 0)?;
+
+        Ok(face
+            .glyph_index(ch)
+            .and_then(|glyph_id| face.glyph_name(glyph_id))
+            .map(str::to_string))
+    }
+
+    /// 列出字体 cmap 中映射到字形的所有字符，按码位排序
+    ///
+    /// 最多返回 `limit` 个字符，避免大型 CJK 字体返回数十万个字符；
+    /// 第二个返回值为实际覆盖的字符总数。
+    pub fn covered_chars(font_path: &Path, limit: usize) -> Result<(Vec<char>, usize)> {
+        let font_data = Self::read_font_data(font_path)?;
+        let (_, sfnt_data) = Self::load_sfnt(&font_data)?;
+        let face = ttf_parser::Face::parse(&sfnt_data, 0)?;
+        let cmap = face.tables().cmap.ok_or(Error::MissingData("cmap 表"))?;
+
+        let mut codepoints = BTreeSet::new();
+        for subtable in cmap.subtables.into_iter().filter(|s| s.is_unicode()) {
+            subtable.codepoints(|codepoint| {
+                if subtable.glyph_index(codepoint).is_some_and(|id| id.0 != 0) {
+                    codepoints.insert(codepoint);
+                }
+            });
+        }
+
+        let chars: Vec<char> = codepoints
+            .iter()
+            .filter_map(|&cp| char::from_u32(cp))
+            .collect();
+        let total = chars.len();
+        Ok((chars.into_iter().take(limit).collect(), total))
+    }
+
+    /// 读取字体的 PostScript 名称和可比较的版本号
+    ///
+    /// 优先解析 name 表中的版本字符串，无法解析时使用 head 表中的修订版本号。
+    pub fn read_version_info(font_path: &Path) -> Result<(String, f64)> {
+        let font_data = Self::read_font_data(font_path)?;
+        let (_, sfnt_data) = Self::load_sfnt(&font_data)?;
+        let face = ttf_parser::Face::parse(&sfnt_data, 0)?;
+
+        let postscript_name = face
+            .names()
+            .into_iter()
+            .filter(|name| name.name_id == ttf_parser::name_id::POST_SCRIPT_NAME)
+            .find_map(|name| name.to_string())
+            .ok_or(Error::MissingData("PostScript 名称"))?;
+        let version = Self::extract_version(&face)
+            .as_deref()
+            .and_then(parse_font_version)
+            .or_else(|| Self::font_revision(&face))
+            .ok_or(Error::MissingData("版本信息"))?;
+
+        Ok((postscript_name, version))
+    }
+
+    /// 读取 head 表的 fontRevision（16.16 定点数）
+    fn font_revision(face: &ttf_parser::Face) -> Option<f64> {
+        let head = face
+            .raw_face()
+            .table(ttf_parser::Tag::from_bytes(b"head"))?;
+        let bytes: [u8; 4] = head.get(4..8)?.try_into().ok()?;
+        Some(i32::from_be_bytes(bytes) as f64 / 65536.0)
+    }
+
+    /// 检查字体的 OS/2 fsType 是否为“受限许可”（禁止嵌入/安装）
+    pub fn is_embedding_restricted(font_path: &Path) -> Result<bool> {
+        let font_data = Self::read_font_data(font_path)?;
+        let (_, sfnt_data) = Self::load_sfnt(&font_data)?;
+        let face = ttf_parser::Face::parse(&sfnt_data, 0)?;
+
+        Ok(face.permissions() == Some(ttf_parser::Permissions::Restricted))
+    }
+
     /// 提取字体名称
-    fn extract_font_name(face: &ttf_parser::Face) -> Result<String, String> {
+    fn extract_font_name(face: &ttf_parser::Face) -> Result<String> {
         // 尝试获取完整字体名称
         for name in face.names() {
             if name.name_id == ttf_parser::name_id::FULL_NAME {
@@ -176,7 +824,15 @@ impl FontParser {
             }
         }
 
-        Err("无法提取字体名称".to_string())
+        Err(Error::MissingData("字体名称"))
+    }
+
+    /// 收集 name 表中的所有名称 ID，已排序去重
+    fn name_record_ids(face: &ttf_parser::Face) -> Vec<u16> {
+        let mut ids: Vec<u16> = face.names().into_iter().map(|name| name.name_id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
     }
 
     /// 提取字体族名称
@@ -203,20 +859,112 @@ impl FontParser {
         None
     }
 
-    /// 判断是否为粗体字体
-    fn is_bold_font(face: &ttf_parser::Face) -> bool {
-        let weight = face.weight();
-        weight.to_number() >= 600
+    /// 提取版本字符串
+    fn extract_version(face: &ttf_parser::Face) -> Option<String> {
+        for name in face.names() {
+            if name.name_id == ttf_parser::name_id::VERSION {
+                if let Some(name_str) = name.to_string() {
+                    return Some(name_str);
+                }
+            }
+        }
+        None
     }
 
-    /// 判断是否为斜体字体
-    fn is_italic_font(face: &ttf_parser::Face) -> bool {
-        face.style() == ttf_parser::Style::Italic || face.style() == ttf_parser::Style::Oblique
+    /// 提取 PostScript 名称
+    fn extract_postscript_name(face: &ttf_parser::Face) -> Option<String> {
+        face.names()
+            .into_iter()
+            .filter(|name| name.name_id == ttf_parser::name_id::POST_SCRIPT_NAME)
+            .find_map(|name| name.to_string())
+    }
+
+    /// 检查字体是否包含轮廓表，位图字体只有 CBDT/EBDT/sbix 等位图表
+    fn has_outlines(face: &ttf_parser::Face) -> bool {
+        let raw_face = face.raw_face();
+        [b"glyf", b"CFF ", b"CFF2"]
+            .iter()
+            .any(|tag| raw_face.table(ttf_parser::Tag::from_bytes(tag)).is_some())
+    }
+
+    /// 列出 cmap 覆盖的书写系统：某个书写系统的代表字符全部映射到字形时视为支持
+    fn supported_scripts(face: &ttf_parser::Face) -> Vec<String> {
+        const SCRIPT_SAMPLES: [(&str, &[char]); 11] = [
+            ("Arabic", &['ا', 'ب', 'ي']),
+            ("Cyrillic", &['А', 'Я', 'а', 'я']),
+            ("Devanagari", &['अ', 'क', 'ह']),
+            ("Greek", &['Α', 'Ω', 'α', 'ω']),
+            ("Han", &['一', '中', '国']),
+            ("Hangul", &['가', '한', '힣']),
+            ("Hebrew", &['א', 'ש', 'ת']),
+            ("Hiragana", &['あ', 'の', 'ん']),
+            ("Katakana", &['ア', 'カ', 'ン']),
+            ("Latin", &['A', 'Z', 'a', 'z']),
+            ("Thai", &['ก', 'ม', 'ฮ']),
+        ];
+
+        SCRIPT_SAMPLES
+            .iter()
+            .filter(|(_, samples)| {
+                samples
+                    .iter()
+                    .all(|&c| face.glyph_index(c).is_some_and(|id| id.0 != 0))
+            })
+            .map(|(script, _)| script.to_string())
+            .collect()
+    }
+
+    /// 列出 GSUB 和 GPOS 表中的特性标签，已排序去重；没有这两个表时返回空列表
+    pub fn feature_tags(face: &ttf_parser::Face) -> Vec<String> {
+        let tables = face.tables();
+        let mut tags: Vec<String> = [tables.gsub, tables.gpos]
+            .into_iter()
+            .flatten()
+            .flat_map(|table| table.features.into_iter().map(|f| f.tag.to_string()))
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// 读取 OS/2 表计算样式标志，缺少 OS/2 表时只按字重和字宽判断
+    fn extract_style_flags(face: &ttf_parser::Face) -> StyleFlags {
+        let weight_class = face.weight().to_number();
+        let width_class = face.width().to_number();
+
+        // fsSelection 位于偏移 62，OBLIQUE 位从版本 4 开始定义
+        let (version, fs_selection) = face
+            .raw_face()
+            .table(ttf_parser::Tag::from_bytes(b"OS/2"))
+            .and_then(|os2| {
+                let version = u16::from_be_bytes(os2.get(0..2)?.try_into().ok()?);
+                let fs_selection = u16::from_be_bytes(os2.get(62..64)?.try_into().ok()?);
+                Some((version, fs_selection))
+            })
+            .unwrap_or((0, 0));
+
+        StyleFlags {
+            bold: fs_selection & 0x0020 != 0 || weight_class >= 600,
+            italic: fs_selection & 0x0001 != 0,
+            oblique: version >= 4 && fs_selection & 0x0200 != 0,
+            condensed: width_class < 5,
+            expanded: width_class > 5,
+            weight_class,
+            width_class,
+        }
     }
 }
 
 /// 格式化字体解析结果
 pub fn format_font_parse_result(result: &FontParseResult) -> String {
+    format_font_parse_result_with_options(result, &FormatOptions::default())
+}
+
+/// 按输出选项格式化字体解析结果
+pub fn format_font_parse_result_with_options(
+    result: &FontParseResult,
+    options: &FormatOptions,
+) -> String {
     let mut output = String::new();
 
     output.push_str("🔤 字体解析结果\n");
@@ -243,6 +991,12 @@ pub fn format_font_parse_result(result: &FontParseResult) -> String {
                 output.push_str(&format!("   样式: {}\n", style));
             }
 
+            output.push_str(&format!("   格式: {}\n", mapping.detected_format.name()));
+
+            if let Some(version) = &mapping.version {
+                output.push_str(&format!("   版本: {}\n", version));
+            }
+
             let mut attributes = Vec::new();
             if mapping.is_bold {
                 attributes.push("粗体");
@@ -276,20 +1030,335 @@ pub fn format_font_parse_result(result: &FontParseResult) -> String {
         output.push_str("ℹ️ 未找到字体文件\n");
     }
 
+    options.apply(&output)
+}
+
+/// 以列对齐的表格格式化字体解析结果（名称、族名、字重、样式、大小）
+///
+/// 过长的名称和族名截断并以省略号结尾；按显示宽度对齐，中日韩字符占两列。
+pub fn format_font_table(result: &FontParseResult) -> String {
+    format_font_table_with_options(result, &FormatOptions::default())
+}
+
+/// 按输出选项以表格格式化字体解析结果
+pub fn format_font_table_with_options(result: &FontParseResult, options: &FormatOptions) -> String {
+    const MAX_TEXT_WIDTH: usize = 24;
+
+    let headers = ["名称", "族名", "字重", "样式", "大小"].map(str::to_string);
+    let rows: Vec<[String; 5]> = result
+        .mappings
+        .iter()
+        .map(|mapping| {
+            let flags = &mapping.style_flags;
+            let style = if flags.oblique {
+                "倾斜"
+            } else if flags.italic || mapping.is_italic {
+                "斜体"
+            } else {
+                "常规"
+            };
+            [
+                truncate_to_width(&mapping.font_name, MAX_TEXT_WIDTH),
+                truncate_to_width(
+                    mapping.family_name.as_deref().unwrap_or("-"),
+                    MAX_TEXT_WIDTH,
+                ),
+                flags.weight_class.to_string(),
+                style.to_string(),
+                format_file_size(mapping.file_size),
+            ]
+        })
+        .collect();
+
+    let mut widths = headers.each_ref().map(|h| display_width(h));
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(display_width(cell));
+        }
+    }
+
+    let format_row = |cells: &[String; 5]| {
+        let mut line = String::new();
+        for (cell, &width) in cells.iter().zip(&widths) {
+            line.push_str(cell);
+            line.push_str(&" ".repeat(width - display_width(cell) + 2));
+        }
+        format!("{}\n", line.trim_end())
+    };
+
+    let mut output = format_row(&headers);
+    let total_width = widths.iter().sum::<usize>() + 2 * (widths.len() - 1);
+    output.push_str(&"-".repeat(total_width));
+    output.push('\n');
+    for row in &rows {
+        output.push_str(&format_row(row));
+    }
+    options.apply(&output)
+}
+
+/// 字符串在等宽终端中的显示宽度，中日韩等宽字符计为 2
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x1100..=0x115F
+            | 0x2E80..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x20000..=0x3FFFD => 2,
+            _ => 1,
+        })
+        .sum()
+}
+
+/// 将字符串截断到不超过 `max_width` 的显示宽度，截断时以省略号结尾
+fn truncate_to_width(text: &str, max_width: usize) -> String {
+    if display_width(text) <= max_width {
+        return text.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut width = 0;
+    for c in text.chars() {
+        let char_width = display_width(c.encode_utf8(&mut [0; 4]));
+        if width + char_width > max_width - 1 {
+            break;
+        }
+        truncated.push(c);
+        width += char_width;
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// 生成 Android fonts.xml 风格的字体族映射
+///
+/// 按族名分组（缺少族名时使用字体名），每个字体以目标文件名列出。
+/// 字重取自 OS/2 的 usWeightClass；未检测样式（字重为 0）时按是否粗体使用 700 或 400。
+pub fn generate_fonts_xml(result: &FontParseResult) -> String {
+    let families = group_by_family(result);
+
+    let mut output = String::new();
+    output.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    output.push_str("<familyset version=\"23\">\n");
+    for (family, mappings) in families {
+        output.push_str(&format!("    <family name=\"{}\">\n", escape_xml(&family)));
+        for mapping in mappings {
+            let file_name = Path::new(&mapping.file_path)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| mapping.file_path.clone());
+            let weight = match mapping.style_flags.weight_class {
+                0 if mapping.is_bold => 700,
+                0 => 400,
+                weight_class => weight_class,
+            };
+            let style = if mapping.is_italic {
+                "italic"
+            } else {
+                "normal"
+            };
+            output.push_str(&format!(
+                "        <font weight=\"{}\" style=\"{}\">{}</font>\n",
+                weight,
+                style,
+                escape_xml(&file_name)
+            ));
+        }
+        output.push_str("    </family>\n");
+    }
+    output.push_str("</familyset>\n");
     output
 }
 
+/// 返回解析结果中最常见的字体格式名称，用于“大多为 TrueType”之类的概括
+///
+/// 数量相同时取名称按字典序最小的格式，没有成功解析的字体时返回 `None`。
+pub fn dominant_format(result: &FontParseResult) -> Option<String> {
+    // BTreeMap 按名称升序迭代，max_by_key 在相等时取最后一个，因此反向迭代
+    group_by_format(result)
+        .into_iter()
+        .rev()
+        .max_by_key(|(_, mappings)| mappings.len())
+        .map(|(name, _)| name)
+}
+
+/// 按默认顺序排序：族名（缺失的排在最后）、字重、正体在前、字体名称
+pub fn sort_font_mappings(mappings: &mut [FontMapping]) {
+    sort_font_mappings_by(mappings, |a, b| {
+        (a.family_name.is_none(), &a.family_name)
+            .cmp(&(b.family_name.is_none(), &b.family_name))
+            .then(a.style_flags.weight_class.cmp(&b.style_flags.weight_class))
+            .then(a.is_italic.cmp(&b.is_italic))
+            .then_with(|| a.font_name.cmp(&b.font_name))
+    });
+}
+
+/// 使用调用方提供的比较函数排序（稳定排序），用于按使用频率等应用自定义顺序排列
+pub fn sort_font_mappings_by<F>(mappings: &mut [FontMapping], cmp: F)
+where
+    F: FnMut(&FontMapping, &FontMapping) -> std::cmp::Ordering,
+{
+    mappings.sort_by(cmp);
+}
+
+/// 按识别出的字体格式分组，键为格式的显示名称（如 "TrueType"、"WOFF2"），组内保持原顺序
+pub fn group_by_format(result: &FontParseResult) -> BTreeMap<String, Vec<&FontMapping>> {
+    let mut groups: BTreeMap<String, Vec<&FontMapping>> = BTreeMap::new();
+    for mapping in &result.mappings {
+        groups
+            .entry(mapping.detected_format.name().to_string())
+            .or_default()
+            .push(mapping);
+    }
+    groups
+}
+
+/// 按族名分组（缺少族名时使用字体名），组内保持原顺序
+pub fn group_by_family(result: &FontParseResult) -> BTreeMap<String, Vec<&FontMapping>> {
+    let mut groups: BTreeMap<String, Vec<&FontMapping>> = BTreeMap::new();
+    for mapping in &result.mappings {
+        let family = mapping.family_name.as_deref().unwrap_or(&mapping.font_name);
+        groups.entry(family.to_string()).or_default().push(mapping);
+    }
+    groups
+}
+
+/// 汇总字体库能显示的书写系统：各字体 `scripts` 的并集，按名称排序
+pub fn aggregate_coverage(result: &FontParseResult) -> Vec<String> {
+    result
+        .mappings
+        .iter()
+        .flat_map(|mapping| mapping.scripts.iter().cloned())
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect()
+}
+
+/// 从 name 表的版本字符串中提取可比较的数值版本
+///
+/// 取第一个数字开始的 `主版本.次版本` 部分，忽略前缀和其后的附加信息，
+/// 如 `"Version 1.00"` → 1.0、`"1.001;hotconv 1.0.88"` → 1.001、`"Version 2.1.3"` → 2.1。
+/// 与 OpenType 约定一致，次版本按小数比较（1.10 等于 1.1）。没有数字时返回 `None`。
+pub fn parse_font_version(version: &str) -> Option<f64> {
+    let start = version.find(|c: char| c.is_ascii_digit())?;
+    let mut seen_dot = false;
+    let end = version[start..]
+        .find(|c: char| {
+            if c == '.' && !seen_dot {
+                seen_dot = true;
+                return false;
+            }
+            !c.is_ascii_digit()
+        })
+        .map_or(version.len(), |offset| start + offset);
+    version[start..end].trim_end_matches('.').parse().ok()
+}
+
+/// 按 CSS 字体匹配规则选出与查询最接近的字体，族名比较忽略大小写
+///
+/// 先按斜体匹配筛选（没有匹配的样式时退回到另一种），再按 CSS 的字重回退顺序选择：
+/// 请求 400–500 时先向上找到 500，再向下，最后向上超过 500；低于 400 时先向下再向上；
+/// 高于 500 时先向上再向下。族名没有匹配时返回 `None`。
+pub fn best_match<'a>(
+    result: &'a FontParseResult,
+    family: &str,
+    weight: u16,
+    italic: bool,
+) -> Option<&'a FontMapping> {
+    let candidates: Vec<&FontMapping> = result
+        .mappings
+        .iter()
+        .filter(|m| {
+            m.family_name
+                .as_deref()
+                .is_some_and(|name| name.eq_ignore_ascii_case(family))
+        })
+        .collect();
+
+    let style_matches = candidates.iter().any(|m| m.is_italic == italic);
+    candidates
+        .into_iter()
+        .filter(|m| !style_matches || m.is_italic == italic)
+        .min_by_key(|m| weight_rank(m.style_flags.weight_class, weight))
+}
+
+/// CSS 字重回退顺序中的排名，越小越优先
+fn weight_rank(candidate: u16, desired: u16) -> (u8, u16) {
+    let distance = candidate.abs_diff(desired);
+    let above = candidate >= desired;
+    if (400..=500).contains(&desired) {
+        if above && candidate <= 500 {
+            (0, distance)
+        } else if !above {
+            (1, distance)
+        } else {
+            (2, distance)
+        }
+    } else if desired < 400 {
+        (if above { 1 } else { 0 }, distance)
+    } else {
+        (if above { 0 } else { 1 }, distance)
+    }
+}
+
+/// 查找近似重复的字体：PostScript 名称和字形数相同，但文件内容不同
+///
+/// 通常是同一字体的不同 hinting 或版本，可用于清理冗余文件。
+/// 按文件大小和内容哈希排除完全相同的副本，每组内相同内容只保留路径最小的一个，
+/// 去除副本后不足两个的组不返回；缺少 PostScript 名称的字体不参与分组。
+pub fn find_near_duplicates(result: &FontParseResult) -> Vec<Vec<FontMapping>> {
+    let mut groups: BTreeMap<(&str, u16), Vec<&FontMapping>> = BTreeMap::new();
+    for mapping in &result.mappings {
+        if let Some(postscript_name) = &mapping.postscript_name {
+            groups
+                .entry((postscript_name, mapping.num_glyphs))
+                .or_default()
+                .push(mapping);
+        }
+    }
+
+    groups
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .filter_map(|mut members| {
+            members.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+            // 逐字节相同的副本只保留路径最小的一个
+            let mut distinct: Vec<FontMapping> = Vec::new();
+            for m in members {
+                let is_copy = distinct.iter().any(|kept| {
+                    kept.file_size == m.file_size
+                        && same_contents(Path::new(&kept.file_path), Path::new(&m.file_path))
+                });
+                if !is_copy {
+                    distinct.push(m.clone());
+                }
+            }
+            (distinct.len() > 1).then_some(distinct)
+        })
+        .collect()
+}
+
+/// 转义 XML 文本和属性中的特殊字符
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 /// 便捷函数：解析字体目录并返回格式化结果
 pub fn parse_fonts_and_format(directory: &str) -> String {
     let result = FontParser::parse_fonts_directory(directory);
     format_font_parse_result(&result)
 }
 
-
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_fonts::TestFont;
     use std::fs::File;
     use tempfile::TempDir;
 
@@ -314,6 +1383,8 @@ mod tests {
         assert!(FontParser::is_font_file(Path::new("calibri.otf")));
         assert!(FontParser::is_font_file(Path::new("roboto.ttc")));
         assert!(FontParser::is_font_file(Path::new("font.otc")));
+        assert!(FontParser::is_font_file(Path::new("web.WOFF2")));
+        assert!(!FontParser::is_font_file(Path::new("legacy.woff")));
         assert!(!FontParser::is_font_file(Path::new("readme.txt")));
         assert!(!FontParser::is_font_file(Path::new("image.png")));
     }
@@ -336,6 +1407,474 @@ mod tests {
         assert!(file_names.contains(&"roboto.ttc".to_string()));
     }
 
+    #[test]
+    fn test_collect_font_files_by_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let font = TestFont::new("Demo");
+        fs::write(
+            temp_dir.path().join("web.woff2"),
+            crate::woff2::tests::encode_sfnt(&font.build()),
+        )
+        .unwrap();
+        // 扩展名不是字体扩展名，但内容是 TrueType
+        font.write_to(&temp_dir.path().join("misnamed.bin"));
+        fs::write(temp_dir.path().join("notes.bin"), b"not a font").unwrap();
+        // 以 "true" 开头的文本不是有效的表目录
+        fs::write(
+            temp_dir.path().join("flag.conf"),
+            b"true\nenabled = yes\nverbose = no\n",
+        )
+        .unwrap();
+        // 暂不支持的 WOFF 不参与解析，不计为失败
+        fs::write(temp_dir.path().join("legacy.woff"), b"wOFF0000").unwrap();
+        fs::write(temp_dir.path().join("legacy.dat"), b"wOFF0000").unwrap();
+        // 没有字体扩展名的 WOFF2 按头部长度识别
+        fs::write(
+            temp_dir.path().join("web.dat"),
+            crate::woff2::tests::encode_sfnt(&font.build()),
+        )
+        .unwrap();
+
+        let mut file_names: Vec<String> = FontParser::collect_font_files(temp_dir.path())
+            .iter()
+            .filter_map(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .collect();
+        file_names.sort();
+        assert_eq!(file_names, ["misnamed.bin", "web.dat", "web.woff2"]);
+
+        let result = FontParser::parse_fonts_directory(temp_dir.path());
+        assert_eq!(result.successful_parses, 3);
+        assert_eq!(result.failed_parses, 0);
+        assert!(result
+            .mappings
+            .iter()
+            .any(|m| m.detected_format == FontFormat::Woff2));
+    }
+
+    #[test]
+    fn test_file_queries_decode_woff2() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("demo.woff2");
+        let mut font = TestFont::new("Demo");
+        font.chars = vec!['A'];
+        font.glyph_names = true;
+        font.fs_type = 0x0002;
+        font.version = "Version 2.000".to_string();
+        fs::write(&path, crate::woff2::tests::encode_sfnt(&font.build())).unwrap();
+
+        assert_eq!(
+            FontParser::name_record(&path, ttf_parser::name_id::FAMILY).unwrap(),
+            Some("Demo".to_string())
+        );
+        assert_eq!(
+            FontParser::glyph_name(&path, 'A').unwrap(),
+            Some("uni0041".to_string())
+        );
+        assert_eq!(
+            FontParser::covered_chars(&path, 10).unwrap(),
+            (vec!['A'], 1)
+        );
+        assert_eq!(
+            FontParser::read_version_info(&path).unwrap(),
+            ("Demo-Regular".to_string(), 2.0)
+        );
+        assert!(FontParser::is_embedding_restricted(&path).unwrap());
+    }
+
+    #[test]
+    fn test_detect_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let cases: [(&str, &[u8], FontFormat); 5] = [
+            ("a.ttf", &[0x00, 0x01, 0x00, 0x00], FontFormat::TrueType),
+            ("b.otf", b"OTTO", FontFormat::OpenTypeCff),
+            ("c.woff", b"wOFF", FontFormat::Woff),
+            ("d.woff2", b"wOF2", FontFormat::Woff2),
+            ("e.ttc", b"ttcf", FontFormat::Collection),
+        ];
+
+        for (name, magic, expected) in cases {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, magic).unwrap();
+            assert_eq!(FontParser::detect_format(&path).unwrap(), expected);
+        }
+
+        let unknown = temp_dir.path().join("f.ttf");
+        fs::write(&unknown, b"fake font data").unwrap();
+        assert!(FontParser::detect_format(&unknown).is_err());
+    }
+
+    #[test]
+    fn test_parse_font_file_uses_content_format() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let real = temp_dir.path().join("real.ttf");
+        TestFont::new("Demo").write_to(&real);
+        let mapping = FontParser::parse_font_file(&real).unwrap();
+        assert_eq!(mapping.detected_format, FontFormat::TrueType);
+        assert_eq!(mapping.family_name.as_deref(), Some("Demo"));
+
+        // 扩展名为 .ttf，实际内容为 WOFF
+        let disguised = temp_dir.path().join("disguised.ttf");
+        fs::write(&disguised, b"wOFF0000").unwrap();
+        let error = FontParser::parse_font_file(&disguised).unwrap_err();
+        assert!(matches!(error, Error::UnsupportedFormat(FontFormat::Woff)));
+        assert!(error.to_string().contains("WOFF"));
+    }
+
+    /// 每次最多返回 3 个字节，相邻两次成功读取之间报告一次中断
+    struct FlakyReader {
+        data: Vec<u8>,
+        position: usize,
+        interrupt_next: bool,
+    }
+
+    impl Read for FlakyReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.interrupt_next = !self.interrupt_next;
+            if !self.interrupt_next {
+                return Err(std::io::ErrorKind::Interrupted.into());
+            }
+            let n = buf.len().min(3).min(self.data.len() - self.position);
+            buf[..n].copy_from_slice(&self.data[self.position..self.position + n]);
+            self.position += n;
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_parse_reader() {
+        let data = TestFont::new("Demo").build();
+        let mapping = FontParser::parse_reader(std::io::Cursor::new(data.clone())).unwrap();
+        assert_eq!(mapping.family_name.as_deref(), Some("Demo"));
+        assert_eq!(mapping.file_size, data.len() as u64);
+
+        // 被中断的读取会重试
+        let reader = FlakyReader {
+            data,
+            position: 0,
+            interrupt_next: false,
+        };
+        assert!(FontParser::parse_reader(reader).is_ok());
+
+        assert!(matches!(
+            FontParser::parse_reader(std::io::Cursor::new(b"not a font".to_vec())),
+            Err(Error::UnknownFormat)
+        ));
+    }
+
+    #[test]
+    fn test_parse_bytes() {
+        let mut font = TestFont::new("Demo");
+        font.weight_class = 700;
+        let data = font.build();
+
+        let mapping = FontParser::parse_bytes(&data).unwrap();
+        assert_eq!(mapping.file_path, "");
+        assert_eq!(mapping.family_name.as_deref(), Some("Demo"));
+        assert_eq!(mapping.file_size, data.len() as u64);
+        assert!(mapping.is_bold);
+
+        assert!(matches!(
+            FontParser::parse_bytes(b"not a font"),
+            Err(Error::UnknownFormat)
+        ));
+    }
+
+    #[test]
+    fn test_read_full_retries_interrupted() {
+        let reader = || FlakyReader {
+            data: b"0123456789".to_vec(),
+            position: 0,
+            interrupt_next: false,
+        };
+
+        assert_eq!(
+            FontParser::read_full(&mut reader(), 10).unwrap(),
+            b"0123456789"
+        );
+
+        // 文件在读取过程中被截断
+        let error = FontParser::read_full(&mut reader(), 16).unwrap_err();
+        assert!(matches!(
+            error,
+            Error::ReadFailed {
+                read: 10,
+                expected: 16
+            }
+        ));
+        assert!(error.to_string().contains("已读取 10 字节，预期 16 字节"));
+    }
+
+    #[test]
+    fn test_feature_tags() {
+        let mut font = TestFont::new("Demo");
+        font.features = vec![*b"smcp", *b"liga", *b"kern", *b"liga"];
+        let mapping = FontParser::parse_bytes(&font.build()).unwrap();
+        assert_eq!(mapping.features, ["kern", "liga", "smcp"]);
+
+        let plain = FontParser::parse_bytes(&TestFont::new("Demo").build()).unwrap();
+        assert!(plain.features.is_empty());
+    }
+
+    #[test]
+    fn test_parse_options_skip_names_and_style() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut font = TestFont::new("Demo");
+        font.weight_class = 700;
+        font.write_to(&temp_dir.path().join("demo.ttf"));
+
+        let options = FontParseOptions {
+            extract_names: false,
+            detect_style: false,
+        };
+        let result = FontParser::parse_fonts_directory_with_options(temp_dir.path(), options);
+        assert_eq!(result.successful_parses, 1);
+        let mapping = &result.mappings[0];
+        assert_eq!(mapping.detected_format, FontFormat::TrueType);
+        assert!(mapping.font_name.is_empty());
+        assert_eq!(mapping.family_name, None);
+        assert_eq!(mapping.postscript_name, None);
+        assert_eq!(mapping.style_flags, StyleFlags::default());
+        assert!(!mapping.is_bold);
+        assert!(mapping.file_size > 0);
+
+        let full = FontParser::parse_fonts_directory(temp_dir.path());
+        assert_eq!(full.mappings[0].family_name.as_deref(), Some("Demo"));
+        assert!(full.mappings[0].is_bold);
+    }
+
+    #[test]
+    fn test_name_record_ids() {
+        let mapping = FontParser::parse_bytes(&TestFont::new("Demo").build()).unwrap();
+        assert_eq!(mapping.name_record_ids, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn test_bitmap_only_font() {
+        let mut font = TestFont::new("Emoji");
+        font.chars = vec!['A', 'B'];
+        font.bitmap_only = true;
+        let mapping = FontParser::parse_bytes(&font.build()).unwrap();
+        assert!(!mapping.has_outlines);
+        assert_eq!(mapping.num_glyphs, 3);
+        assert_eq!(mapping.font_name, "Emoji Regular");
+
+        font.bitmap_only = false;
+        assert!(FontParser::parse_bytes(&font.build()).unwrap().has_outlines);
+    }
+
+    #[test]
+    fn test_style_flags() {
+        let temp_dir = TempDir::new().unwrap();
+        let flags = |font: TestFont| {
+            let path = temp_dir.path().join("style.ttf");
+            font.write_to(&path);
+            FontParser::parse_font_file(&path).unwrap().style_flags
+        };
+
+        let regular = flags(TestFont::new("Demo"));
+        assert_eq!(
+            regular,
+            StyleFlags {
+                weight_class: 400,
+                width_class: 5,
+                ..Default::default()
+            }
+        );
+
+        // fsSelection 的 BOLD 位或足够的字重都视为粗体
+        let mut font = TestFont::new("Demo");
+        font.fs_selection = 0x0020;
+        assert!(flags(font).bold);
+        let mut font = TestFont::new("Demo");
+        font.weight_class = 600;
+        let semibold = flags(font);
+        assert!(semibold.bold);
+        assert_eq!(semibold.weight_class, 600);
+        let mut font = TestFont::new("Demo");
+        font.weight_class = 300;
+        assert!(!flags(font).bold);
+
+        let mut font = TestFont::new("Demo");
+        font.fs_selection = 0x0001;
+        let italic = flags(font);
+        assert!(italic.italic && !italic.oblique);
+
+        // OBLIQUE 位只在 OS/2 版本 4 及以上有效
+        let mut font = TestFont::new("Demo");
+        font.fs_selection = 0x0200;
+        assert!(!flags(font.clone()).oblique);
+        font.os2_version = 4;
+        let oblique = flags(font);
+        assert!(oblique.oblique && !oblique.italic);
+
+        let mut font = TestFont::new("Demo");
+        font.width_class = 3;
+        let condensed = flags(font);
+        assert!(condensed.condensed && !condensed.expanded);
+        let mut font = TestFont::new("Demo");
+        font.width_class = 7;
+        let expanded = flags(font);
+        assert!(expanded.expanded && !expanded.condensed);
+    }
+
+    #[test]
+    fn test_name_record() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("demo.ttf");
+        TestFont::new("Demo").write_to(&path);
+
+        assert_eq!(
+            FontParser::name_record(&path, ttf_parser::name_id::FAMILY).unwrap(),
+            Some("Demo".to_string())
+        );
+        assert_eq!(
+            FontParser::name_record(&path, ttf_parser::name_id::LICENSE_URL).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_covered_chars() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("demo.ttf");
+        let mut font = TestFont::new("Demo");
+        font.chars = vec!['中', 'B', 'A'];
+        font.write_to(&path);
+
+        let (chars, total) = FontParser::covered_chars(&path, 100).unwrap();
+        assert_eq!(chars, ['A', 'B', '中']);
+        assert_eq!(total, 3);
+
+        let (chars, total) = FontParser::covered_chars(&path, 2).unwrap();
+        assert_eq!(chars, ['A', 'B']);
+        assert_eq!(total, 3);
+
+        // 没有 cmap 表的字体
+        TestFont::new("Empty").write_to(&path);
+        assert!(matches!(
+            FontParser::covered_chars(&path, 100),
+            Err(Error::MissingData(_))
+        ));
+    }
+
+    #[test]
+    fn test_glyph_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let named = temp_dir.path().join("named.ttf");
+        let unnamed = temp_dir.path().join("unnamed.ttf");
+        let mut font = TestFont::new("Demo");
+        font.chars = vec!['A'];
+        font.write_to(&unnamed);
+        font.glyph_names = true;
+        font.write_to(&named);
+
+        assert_eq!(
+            FontParser::glyph_name(&named, 'A').unwrap(),
+            Some("uni0041".to_string())
+        );
+        assert_eq!(FontParser::glyph_name(&named, 'Z').unwrap(), None);
+        assert_eq!(FontParser::glyph_name(&unnamed, 'A').unwrap(), None);
+    }
+
+    #[test]
+    fn test_render_sample() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("demo.ttf");
+        let mut font = TestFont::new("Demo");
+        font.chars = vec!['A', 'B'];
+        font.write_to(&path);
+
+        // 每个字形前进 500/1000 em，上升 800、下降 200
+        let bitmap = FontParser::render_sample(&path, "AB", 20).unwrap();
+        assert_eq!((bitmap.width, bitmap.height), (20, 20));
+        assert_eq!(bitmap.pixels.len(), 400);
+        assert!(bitmap.pixels.iter().any(|&p| p > 0));
+
+        let bytes = bitmap.to_bytes();
+        assert_eq!(&bytes[..8], &[0, 0, 0, 20, 0, 0, 0, 20]);
+
+        assert!(FontParser::render_sample(&temp_dir.path().join("missing.ttf"), "A", 20).is_err());
+
+        // WOFF2 先解码为 sfnt 再渲染
+        let woff2_path = temp_dir.path().join("demo.woff2");
+        fs::write(&woff2_path, crate::woff2::tests::encode_sfnt(&font.build())).unwrap();
+        let woff2_bitmap = FontParser::render_sample(&woff2_path, "AB", 20).unwrap();
+        assert_eq!(woff2_bitmap.pixels, bitmap.pixels);
+
+        // 截断的字体返回 Truncated 而不是交给渲染器
+        let truncated_path = temp_dir.path().join("truncated.ttf");
+        let data = font.build();
+        fs::write(&truncated_path, &data[..data.len() - 8]).unwrap();
+        assert!(matches!(
+            FontParser::render_sample(&truncated_path, "AB", 20),
+            Err(Error::Truncated { .. })
+        ));
+
+        // 字号过大或文本过长时拒绝分配
+        assert!(matches!(
+            FontParser::render_sample(&path, "AB", 100_000),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            FontParser::render_sample(&path, &"A".repeat(10_000), 2_000),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            FontParser::render_sample(&path, "A", u32::MAX),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_fonts_cached() {
+        let font_dir = TempDir::new().unwrap();
+        let cache_dir = TempDir::new().unwrap();
+        let cache_path = cache_dir.path().join("cache.json");
+        let font_path = font_dir.path().join("demo.ttf");
+        let mtime = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+
+        let write_font = |family: &str, mtime| {
+            TestFont::new(family).write_to(&font_path);
+            File::options()
+                .write(true)
+                .open(&font_path)
+                .unwrap()
+                .set_modified(mtime)
+                .unwrap();
+        };
+        let families = |result: &FontParseResult| -> Vec<String> {
+            result
+                .mappings
+                .iter()
+                .filter_map(|m| m.family_name.clone())
+                .collect()
+        };
+
+        // 未命中：首次解析并写入缓存
+        write_font("First", mtime);
+        let result = FontParser::parse_fonts_cached(font_dir.path(), &cache_path);
+        assert_eq!(families(&result), ["First"]);
+        assert!(cache_path.exists());
+
+        // 命中：内容变化但修改时间不变，返回缓存结果
+        write_font("Second", mtime);
+        let result = FontParser::parse_fonts_cached(font_dir.path(), &cache_path);
+        assert_eq!(families(&result), ["First"]);
+
+        // 失效：修改时间变化后重新解析
+        write_font("Second", mtime + std::time::Duration::from_secs(60));
+        let result = FontParser::parse_fonts_cached(font_dir.path(), &cache_path);
+        assert_eq!(families(&result), ["Second"]);
+
+        // 删除的文件从缓存中移除
+        fs::remove_file(&font_path).unwrap();
+        let result = FontParser::parse_fonts_cached(font_dir.path(), &cache_path);
+        assert_eq!(result.total_files, 0);
+        assert!(FontParser::load_cache(&cache_path).is_empty());
+    }
+
     #[test]
     fn test_format_empty_result() {
         let result = FontParseResult {
@@ -349,4 +1888,474 @@ mod tests {
         let formatted = format_font_parse_result(&result);
         assert!(formatted.contains("未找到字体文件"));
     }
+
+    #[test]
+    fn test_convert_woff2_to_ttf() {
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("demo.woff2");
+        let dst = temp_dir.path().join("demo.ttf");
+        let mut font = TestFont::new("Demo");
+        font.chars = vec!['A', 'B'];
+        fs::write(&src, crate::woff2::tests::encode_sfnt(&font.build())).unwrap();
+
+        FontParser::convert_woff2_to_ttf(&src, &dst).unwrap();
+        assert_eq!(
+            FontParser::detect_format(&dst).unwrap(),
+            FontFormat::TrueType
+        );
+        let mapping = FontParser::parse_font_file(&dst).unwrap();
+        assert_eq!(mapping.family_name.as_deref(), Some("Demo"));
+        assert_eq!(mapping.num_glyphs, 3);
+
+        // 非 WOFF2 源文件被拒绝
+        assert!(matches!(
+            FontParser::convert_woff2_to_ttf(&dst, &temp_dir.path().join("out.ttf")),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_truncated_font() {
+        let temp_dir = TempDir::new().unwrap();
+        let data = TestFont::new("Demo").build();
+        let path = temp_dir.path().join("partial.ttf");
+
+        // 表数据只写入了一半
+        fs::write(&path, &data[..data.len() / 2]).unwrap();
+        match FontParser::parse_font_file(&path) {
+            Err(Error::Truncated { expected, actual }) => {
+                assert_eq!(actual, (data.len() / 2) as u64);
+                assert!(expected > actual);
+            }
+            other => panic!("应返回 Truncated，实际为 {:?}", other),
+        }
+
+        // 连表目录都不完整
+        fs::write(&path, &data[..20]).unwrap();
+        assert!(matches!(
+            FontParser::parse_font_file(&path),
+            Err(Error::Truncated { actual: 20, .. })
+        ));
+
+        // 写入完成后可正常解析
+        fs::write(&path, &data).unwrap();
+        assert!(FontParser::parse_font_file(&path).is_ok());
+
+        // 偏移与长度之和超出 32 位范围时同样视为不完整，而不是回绕后通过检查
+        let mut overflowing = data.clone();
+        overflowing[12 + 8..12 + 16]
+            .copy_from_slice(&[0xFF, 0xFF, 0xFF, 0xF0, 0x00, 0x00, 0x00, 0x20]);
+        assert!(matches!(
+            FontParser::check_truncated(&overflowing, FontFormat::TrueType),
+            Err(Error::Truncated {
+                expected: 0x1_0000_0010,
+                ..
+            })
+        ));
+
+        // 集合文件中指向文件末尾之后的字体偏移
+        let mut collection = b"ttcf".to_vec();
+        collection.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 1, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert!(matches!(
+            FontParser::check_truncated(&collection, FontFormat::Collection),
+            Err(Error::Truncated {
+                expected: 0x1_0000_0005,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_font_version() {
+        for (input, expected) in [
+            ("Version 1.00", Some(1.0)),
+            (
+                "Version 1.000;PS 001.000;hotconv 1.0.88;makeotf.lib2.5.64775",
+                Some(1.0),
+            ),
+            ("1.001;hotconv", Some(1.001)),
+            ("Version 2.137;GOOG;noto-source:20170915", Some(2.137)),
+            ("Version 3.1.2", Some(3.1)),
+            ("v5", Some(5.0)),
+            ("Version 7. ", Some(7.0)),
+            ("OTF 1.018;PS 001.001;Core 1.0.31", Some(1.018)),
+            ("Unknown", None),
+            ("", None),
+        ] {
+            assert_eq!(parse_font_version(input), expected, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_read_version_info_prefers_name_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("demo.ttf");
+        let mut font = TestFont::new("Demo");
+        font.version = "Version 3.002;hotconv 1.0.109".to_string();
+        font.revision = 1.0;
+        font.write_to(&path);
+        assert_eq!(
+            FontParser::read_version_info(&path).unwrap(),
+            ("Demo-Regular".to_string(), 3.002)
+        );
+
+        // 版本字符串无法解析时回退到 head 表
+        font.version = "Unknown".to_string();
+        font.revision = 2.5;
+        font.write_to(&path);
+        assert_eq!(FontParser::read_version_info(&path).unwrap().1, 2.5);
+    }
+
+    #[test]
+    fn test_find_near_duplicates() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut font = TestFont::new("Demo");
+        font.write_to(&temp_dir.path().join("demo-a.ttf"));
+        font.write_to(&temp_dir.path().join("demo-copy.ttf"));
+        font.revision = 2.0;
+        font.write_to(&temp_dir.path().join("demo-b.ttf"));
+        TestFont::new("Other").write_to(&temp_dir.path().join("other.ttf"));
+
+        let result = FontParser::parse_fonts_directory(temp_dir.path());
+        let groups = find_near_duplicates(&result);
+        assert_eq!(groups.len(), 1);
+        assert!(groups[0]
+            .iter()
+            .all(|m| m.postscript_name.as_deref() == Some("Demo-Regular")));
+        // demo-copy.ttf 与 demo-a.ttf 内容完全相同，只保留路径较小的 demo-a.ttf
+        let file_names: Vec<&str> = groups[0]
+            .iter()
+            .map(|m| {
+                Path::new(&m.file_path)
+                    .file_name()
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(file_names, ["demo-a.ttf", "demo-b.ttf"]);
+
+        // 完全相同的副本不算近似重复
+        std::fs::remove_file(temp_dir.path().join("demo-b.ttf")).unwrap();
+        let result = FontParser::parse_fonts_directory(temp_dir.path());
+        assert!(find_near_duplicates(&result).is_empty());
+    }
+
+    #[test]
+    fn test_dominant_format() {
+        let with_formats = |formats: &[FontFormat]| {
+            let template = FontParser::parse_bytes(&TestFont::new("Demo").build()).unwrap();
+            FontParseResult {
+                total_files: formats.len(),
+                successful_parses: formats.len(),
+                failed_parses: 0,
+                mappings: formats
+                    .iter()
+                    .map(|&detected_format| FontMapping {
+                        detected_format,
+                        ..template.clone()
+                    })
+                    .collect(),
+                errors: Vec::new(),
+            }
+        };
+
+        let skewed = with_formats(&[
+            FontFormat::Woff2,
+            FontFormat::TrueType,
+            FontFormat::TrueType,
+            FontFormat::OpenTypeCff,
+            FontFormat::TrueType,
+        ]);
+        assert_eq!(dominant_format(&skewed).as_deref(), Some("TrueType"));
+
+        // 数量相同时结果稳定，取名称字典序最小的
+        let tied = with_formats(&[FontFormat::Woff2, FontFormat::TrueType]);
+        assert_eq!(dominant_format(&tied).as_deref(), Some("TrueType"));
+        let tied = with_formats(&[FontFormat::TrueType, FontFormat::OpenTypeCff]);
+        assert_eq!(dominant_format(&tied).as_deref(), Some("OpenType (CFF)"));
+
+        assert_eq!(dominant_format(&with_formats(&[])), None);
+    }
+
+    #[test]
+    fn test_sort_font_mappings() {
+        let template = FontParser::parse_bytes(&TestFont::new("Demo").build()).unwrap();
+        let mapping =
+            |name: &str, family: Option<&str>, weight_class: u16, italic: bool| FontMapping {
+                font_name: name.to_string(),
+                family_name: family.map(str::to_string),
+                is_italic: italic,
+                style_flags: StyleFlags {
+                    weight_class,
+                    ..Default::default()
+                },
+                ..template.clone()
+            };
+        let mut mappings = vec![
+            mapping("Unnamed", None, 400, false),
+            mapping("Beta Bold", Some("Beta"), 700, false),
+            mapping("Alpha Italic", Some("Alpha"), 400, true),
+            mapping("Beta Regular", Some("Beta"), 400, false),
+            mapping("Alpha Regular", Some("Alpha"), 400, false),
+        ];
+        let names = |mappings: &[FontMapping]| -> Vec<String> {
+            mappings.iter().map(|m| m.font_name.clone()).collect()
+        };
+
+        sort_font_mappings(&mut mappings);
+        assert_eq!(
+            names(&mappings),
+            [
+                "Alpha Regular",
+                "Alpha Italic",
+                "Beta Regular",
+                "Beta Bold",
+                "Unnamed"
+            ]
+        );
+
+        // 自定义顺序：按使用次数降序，次数相同的保持原顺序
+        let usage: HashMap<&str, u32> = [("Beta Bold", 10), ("Unnamed", 3)].into_iter().collect();
+        sort_font_mappings_by(&mut mappings, |a, b| {
+            let count = |m: &FontMapping| usage.get(m.font_name.as_str()).copied().unwrap_or(0);
+            count(b).cmp(&count(a))
+        });
+        assert_eq!(
+            names(&mappings),
+            [
+                "Beta Bold",
+                "Unnamed",
+                "Alpha Regular",
+                "Alpha Italic",
+                "Beta Regular"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_coverage() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut latin = TestFont::new("Latin Sans");
+        latin.chars = ('A'..='Z').chain('a'..='z').collect();
+        latin.write_to(&temp_dir.path().join("latin.ttf"));
+        let mut cjk = TestFont::new("CJK Sans");
+        cjk.chars = vec!['一', '中', '国', 'あ', 'の'];
+        cjk.write_to(&temp_dir.path().join("cjk.ttf"));
+
+        let result = FontParser::parse_fonts_directory(temp_dir.path());
+        let scripts_of = |family: &str| {
+            result
+                .mappings
+                .iter()
+                .find(|m| m.family_name.as_deref() == Some(family))
+                .unwrap()
+                .scripts
+                .clone()
+        };
+        assert_eq!(scripts_of("Latin Sans"), ["Latin"]);
+        // 缺少 ん，平假名不算支持
+        assert_eq!(scripts_of("CJK Sans"), ["Han"]);
+        assert_eq!(aggregate_coverage(&result), ["Han", "Latin"]);
+    }
+
+    #[test]
+    fn test_group_by_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let sfnt = TestFont::new("Demo").build();
+        fs::write(temp_dir.path().join("a.ttf"), &sfnt).unwrap();
+        fs::write(temp_dir.path().join("b.ttf"), &sfnt).unwrap();
+        let mut otf = sfnt.clone();
+        otf[..4].copy_from_slice(b"OTTO");
+        fs::write(temp_dir.path().join("c.otf"), &otf).unwrap();
+
+        let result = FontParser::parse_fonts_directory(temp_dir.path());
+        let groups = group_by_format(&result);
+        let summary: Vec<(&str, Vec<&str>)> = groups
+            .iter()
+            .map(|(format, mappings)| {
+                // 目录遍历顺序不固定，按文件名排序后比较
+                let mut files: Vec<&str> = mappings
+                    .iter()
+                    .map(|m| {
+                        Path::new(&m.file_path)
+                            .file_name()
+                            .unwrap()
+                            .to_str()
+                            .unwrap()
+                    })
+                    .collect();
+                files.sort();
+                (format.as_str(), files)
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("OpenType (CFF)", vec!["c.otf"]),
+                ("TrueType", vec!["a.ttf", "b.ttf"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_best_match() {
+        let mapping = |name: &str, weight_class: u16, italic: bool| FontMapping {
+            file_path: format!("/fonts/{}.ttf", name),
+            detected_format: FontFormat::TrueType,
+            font_name: name.to_string(),
+            family_name: Some("Demo".to_string()),
+            style_name: None,
+            version: None,
+            is_bold: weight_class >= 600,
+            is_italic: italic,
+            style_flags: StyleFlags {
+                italic,
+                weight_class,
+                ..Default::default()
+            },
+            postscript_name: None,
+            features: Vec::new(),
+            num_glyphs: 0,
+            file_size: 0,
+            has_outlines: true,
+            name_record_ids: Vec::new(),
+            scripts: Vec::new(),
+        };
+        let result = FontParseResult {
+            total_files: 5,
+            successful_parses: 5,
+            failed_parses: 0,
+            mappings: vec![
+                mapping("Light", 300, false),
+                mapping("Regular", 400, false),
+                mapping("Bold", 700, false),
+                mapping("Black", 900, false),
+                mapping("Italic", 400, true),
+            ],
+            errors: Vec::new(),
+        };
+        let best = |family: &str, weight: u16, italic: bool| {
+            best_match(&result, family, weight, italic).map(|m| m.font_name.as_str())
+        };
+
+        // 精确匹配，族名忽略大小写
+        assert_eq!(best("Demo", 700, false), Some("Bold"));
+        assert_eq!(best("demo", 400, true), Some("Italic"));
+        // 没有 500 时先向下找到 400，而不是向上找 700
+        assert_eq!(best("Demo", 500, false), Some("Regular"));
+        // 高于 500 时优先向上
+        assert_eq!(best("Demo", 600, false), Some("Bold"));
+        assert_eq!(best("Demo", 800, false), Some("Black"));
+        // 低于 400 时优先向下，没有更细时再向上
+        assert_eq!(best("Demo", 350, false), Some("Light"));
+        assert_eq!(best("Demo", 100, false), Some("Light"));
+        // 没有斜体粗体时退回斜体中最接近的
+        assert_eq!(best("Demo", 700, true), Some("Italic"));
+        assert_eq!(best("Other", 400, false), None);
+    }
+
+    #[test]
+    fn test_format_font_table() {
+        let mapping = |name: &str, family: &str, weight_class: u16, italic: bool| FontMapping {
+            file_path: format!("/fonts/{}.ttf", name),
+            detected_format: FontFormat::TrueType,
+            font_name: name.to_string(),
+            family_name: Some(family.to_string()),
+            style_name: None,
+            version: None,
+            is_bold: weight_class >= 600,
+            is_italic: italic,
+            style_flags: StyleFlags {
+                italic,
+                weight_class,
+                ..Default::default()
+            },
+            postscript_name: None,
+            features: Vec::new(),
+            num_glyphs: 0,
+            file_size: 2048,
+            has_outlines: true,
+            name_record_ids: Vec::new(),
+            scripts: Vec::new(),
+        };
+        let result = FontParseResult {
+            total_files: 3,
+            successful_parses: 3,
+            failed_parses: 0,
+            mappings: vec![
+                mapping("A", "Demo", 400, false),
+                mapping("思源黑体 Bold", "思源黑体", 700, false),
+                mapping("An Extremely Long Font Name Italic", "Demo", 400, true),
+            ],
+            errors: Vec::new(),
+        };
+
+        let table = format_font_table(&result);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "名称                      族名      字重  样式  大小",
+                "-------------------------------------------------------",
+                "A                         Demo      400   常规  2.00 KB",
+                "思源黑体 Bold             思源黑体  700   常规  2.00 KB",
+                "An Extremely Long Font …  Demo      400   斜体  2.00 KB",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_fonts_xml() {
+        let mapping = |file: &str, family: &str, weight_class: u16, italic: bool| FontMapping {
+            file_path: format!("/fonts/{}", file),
+            detected_format: FontFormat::TrueType,
+            font_name: file.to_string(),
+            family_name: Some(family.to_string()),
+            style_name: None,
+            version: None,
+            is_bold: weight_class >= 600,
+            is_italic: italic,
+            style_flags: StyleFlags {
+                weight_class,
+                ..Default::default()
+            },
+            postscript_name: None,
+            features: Vec::new(),
+            num_glyphs: 0,
+            file_size: 0,
+            has_outlines: true,
+            name_record_ids: Vec::new(),
+            scripts: Vec::new(),
+        };
+        let result = FontParseResult {
+            total_files: 5,
+            successful_parses: 5,
+            failed_parses: 0,
+            mappings: vec![
+                mapping("Demo-Light.ttf", "Demo", 300, false),
+                mapping("Demo-Regular.ttf", "Demo", 400, false),
+                mapping("Demo-SemiBold.ttf", "Demo", 600, false),
+                mapping("Demo-BoldItalic.ttf", "Demo", 700, true),
+                mapping("Other.ttf", "A&B", 0, false),
+            ],
+            errors: Vec::new(),
+        };
+
+        let xml = generate_fonts_xml(&result);
+        assert!(xml.contains(concat!(
+            "    <family name=\"Demo\">\n",
+            "        <font weight=\"300\" style=\"normal\">Demo-Light.ttf</font>\n",
+            "        <font weight=\"400\" style=\"normal\">Demo-Regular.ttf</font>\n",
+            "        <font weight=\"600\" style=\"normal\">Demo-SemiBold.ttf</font>\n",
+            "        <font weight=\"700\" style=\"italic\">Demo-BoldItalic.ttf</font>\n",
+            "    </family>\n",
+        )));
+        // 未检测样式时回退到 400
+        assert!(xml.contains(concat!(
+            "    <family name=\"A&amp;B\">\n",
+            "        <font weight=\"400\" style=\"normal\">Other.ttf</font>\n",
+        )));
+        assert!(xml.ends_with("</familyset>\n"));
+    }
 }
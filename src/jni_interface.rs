@@ -1,15 +1,24 @@
-use jni::objects::{JClass, JString};
-use jni::sys::jstring;
+use jni::objects::{JByteArray, JClass, JObject, JString, JValue};
+use jni::sys::{jbyteArray, jint, jstring};
 use jni::JNIEnv;
-use log::{error, info};
-use std::sync::Once;
+use log::{error, info, warn};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Once, OnceLock};
 
-use crate::font_copy::copy_font_files;
-use crate::font_parser::parse_fonts_and_format;
-use crate::scanner::{format_file_size, DirectoryScanner};
+use crate::error::Error;
+use crate::font_copy::{format_copy_result_with_options, FontCopier};
+use crate::font_parser::{format_font_parse_result_with_options, group_by_family, FontParser};
+use crate::scanner::{
+    format_fonts_info, path_list, top_files_by_size, DirectoryScanner, FormatOptions, IconTheme,
+    LineEnding, ScanConfig,
+};
 
 static INIT_LOGGER: Once = Once::new();
 
+/// 通过 `setLogTag` 设置的 logcat 标签，未设置时使用 "RustDemo"
+static LOG_TAG: OnceLock<String> = OnceLock::new();
+
 /// 初始化日志记录器 - 只初始化一次
 fn init_logger() {
     INIT_LOGGER.call_once(|| {
@@ -19,17 +28,76 @@ fn init_logger() {
             android_logger::init_once(
                 android_logger::Config::default()
                     .with_max_level(log::LevelFilter::Debug)
-                    .with_tag("RustDemo"),
+                    .with_tag(LOG_TAG.get().map_or("RustDemo", String::as_str))
+                    .format(|f, record| {
+                        write!(f, "{}{}", record.args(), format_key_values(record))
+                    }),
             );
         }
 
         #[cfg(not(target_os = "android"))]
         {
-            let _ = env_logger::try_init();
+            use std::io::Write;
+            let _ = env_logger::Builder::from_default_env()
+                .format(|buf, record| {
+                    writeln!(
+                        buf,
+                        "[{} {}] {}{}",
+                        record.level(),
+                        record.target(),
+                        record.args(),
+                        format_key_values(record)
+                    )
+                })
+                .try_init();
         }
     });
 }
 
+/// 设置日志标签，只在日志初始化前第一次设置时生效
+fn set_log_tag(tag: String) -> bool {
+    if tag.is_empty() || INIT_LOGGER.is_completed() {
+        return false;
+    }
+    LOG_TAG.set(tag).is_ok()
+}
+
+/// JNI函数 - 设置 logcat 中使用的日志标签
+///
+/// 必须在第一次调用其他会输出日志的 JNI 函数之前调用，之后的调用无效并返回 false。
+/// 桌面平台使用 env_logger，不受标签影响。
+#[no_mangle]
+pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_setLogTag(
+    mut env: JNIEnv,
+    _class: JClass,
+    tag: JString,
+) -> bool {
+    match env.get_string(&tag) {
+        Ok(java_str) => set_log_tag(java_str.into()),
+        Err(_) => false,
+    }
+}
+
+/// 将日志记录中的结构化字段渲染为 ` key=value` 形式，便于在 logcat 中按字段过滤
+fn format_key_values(record: &log::Record) -> String {
+    struct Collector(String);
+
+    impl<'kvs> log::kv::VisitSource<'kvs> for Collector {
+        fn visit_pair(
+            &mut self,
+            key: log::kv::Key<'kvs>,
+            value: log::kv::Value<'kvs>,
+        ) -> Result<(), log::kv::Error> {
+            self.0.push_str(&format!(" {}={}", key, value));
+            Ok(())
+        }
+    }
+
+    let mut collector = Collector(String::new());
+    let _ = record.key_values().visit(&mut collector);
+    collector.0
+}
+
 /// 创建Java字符串
 fn create_java_string(env: &mut JNIEnv, s: &str) -> jstring {
     match env.new_string(s) {
@@ -44,37 +112,26 @@ fn create_java_string(env: &mut JNIEnv, s: &str) -> jstring {
     }
 }
 
-/// 简化的字体信息加载
-fn load_fonts_info(directory: &str) -> String {
-    init_logger();
-
-    info!("扫描目录: {}", directory);
+/// 返回给 Java 的格式化文本的最大字节数，避免大型目录生成数 MB 的 jstring
+const MAX_OUTPUT_BYTES: usize = 256 * 1024;
 
-    let font_files = DirectoryScanner::scan_fonts(directory);
-
-    if font_files.is_empty() {
-        return format!("📁 目录: {}\n❌ 未找到字体文件", directory);
+/// JNI 返回格式化文本时使用的选项，超过 `MAX_OUTPUT_BYTES` 的输出会被截断
+fn output_options() -> FormatOptions {
+    FormatOptions {
+        max_output_bytes: Some(MAX_OUTPUT_BYTES),
+        ..Default::default()
     }
+}
 
-    let mut output = String::new();
-    output.push_str(&format!("🗡🗡🗡 Rust库\n"));
-    output.push_str(&format!("📁 目录: {}\n", directory));
-    output.push_str(&format!("🔤 找到 {} 个字体文件:\n\n", font_files.len()));
-
-    let total_size: u64 = font_files.iter().map(|f| f.size).sum();
-
-    for file in &font_files {
-        let ext = file.extension.as_deref().unwrap_or("unknown");
-        output.push_str(&format!(
-            "• {} ({}) - {}\n",
-            file.name,
-            ext.to_uppercase(),
-            format_file_size(file.size)
-        ));
+/// 创建Java字节数组
+fn create_java_byte_array(env: &mut JNIEnv, bytes: &[u8]) -> jbyteArray {
+    match env.byte_array_from_slice(bytes) {
+        Ok(array) => array.into_raw(),
+        Err(e) => {
+            error!("创建Java字节数组失败: {}", e);
+            std::ptr::null_mut()
+        }
     }
-
-    output.push_str(&format!("\n📊 总计: {}", format_file_size(total_size)));
-    output
 }
 
 /// JNI函数 - 加载字体信息（保持向后兼容）
@@ -93,10 +150,65 @@ pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_loadFontsInfo(
         }
     };
 
-    let result = load_fonts_info(&directory_str);
+    init_logger();
+    let result = format_fonts_info(&directory_str, None, &output_options());
     create_java_string(&mut env, &result)
 }
 
+/// JNI函数 - 按选项加载字体信息
+///
+/// `maxListed` 小于 0 时列出全部文件；`asciiHeaders` 使用 ASCII 图标代替 emoji，
+/// `crlfLineEndings` 使用 `\r\n` 换行，便于直接分享到 Windows 工具中查看。
+/// 与其他返回文本的 JNI 函数一样，输出超过 `MAX_OUTPUT_BYTES` 时截断。
+#[no_mangle]
+pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_loadFontsInfoWithOptions(
+    mut env: JNIEnv,
+    _class: JClass,
+    directory: JString,
+    max_listed: jint,
+    ascii_headers: bool,
+    crlf_line_endings: bool,
+) -> jstring {
+    init_logger();
+
+    let directory_str: String = match env.get_string(&directory) {
+        Ok(java_str) => java_str.into(),
+        Err(e) => {
+            let error_msg = format!("参数转换失败: {}", e);
+            error!("{}", error_msg);
+            return create_java_string(&mut env, &error_msg);
+        }
+    };
+
+    let result =
+        load_fonts_info_with_options(&directory_str, max_listed, ascii_headers, crlf_line_endings);
+    create_java_string(&mut env, &result)
+}
+
+/// 将 JNI 参数转换为 [`FormatOptions`] 后格式化字体信息
+fn load_fonts_info_with_options(
+    directory: &str,
+    max_listed: jint,
+    ascii_headers: bool,
+    crlf_line_endings: bool,
+) -> String {
+    let options = FormatOptions {
+        icons: if ascii_headers {
+            IconTheme::ascii()
+        } else {
+            IconTheme::default()
+        },
+        line_ending: if crlf_line_endings {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Lf
+        },
+        ..output_options()
+    };
+    let max_listed = usize::try_from(max_listed).ok();
+    format_fonts_info(directory, max_listed, &options)
+}
+
 /// JNI函数 - 复制字体文件
 #[no_mangle]
 pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_copyFontFiles(
@@ -131,10 +243,54 @@ pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_copyFontFiles(
         source_dir_str, target_dir_str, overwrite_existing
     );
 
-    let result = copy_font_files(&source_dir_str, &target_dir_str, overwrite_existing);
+    let result = FontCopier::new(overwrite_existing).copy_fonts(&source_dir_str, &target_dir_str);
+    let result = format_copy_result_with_options(&result, &output_options());
+    create_java_string(&mut env, &result)
+}
+
+/// JNI函数 - 复制字体文件并以 JSON 返回完整的复制结果
+#[no_mangle]
+pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_copyFontsJson(
+    mut env: JNIEnv,
+    _class: JClass,
+    source_directory: JString,
+    target_directory: JString,
+    overwrite_existing: bool,
+) -> jstring {
+    init_logger();
+
+    let source_dir_str: String = match env.get_string(&source_directory) {
+        Ok(java_str) => java_str.into(),
+        Err(e) => {
+            let error_msg = format!("源目录参数转换失败: {}", e);
+            error!("{}", error_msg);
+            return create_java_string(&mut env, &json_error(&error_msg));
+        }
+    };
+
+    let target_dir_str: String = match env.get_string(&target_directory) {
+        Ok(java_str) => java_str.into(),
+        Err(e) => {
+            let error_msg = format!("目标目录参数转换失败: {}", e);
+            error!("{}", error_msg);
+            return create_java_string(&mut env, &json_error(&error_msg));
+        }
+    };
+
+    let result = copy_fonts_json(&source_dir_str, &target_dir_str, overwrite_existing);
     create_java_string(&mut env, &result)
 }
 
+/// 复制字体文件并将 `CopyResult` 序列化为 JSON，序列化失败时返回错误 JSON
+fn copy_fonts_json(source_dir: &str, target_dir: &str, overwrite: bool) -> String {
+    let result = FontCopier::new(overwrite).copy_fonts(source_dir, target_dir);
+    serde_json::to_string(&result).unwrap_or_else(|e| {
+        let error_msg = format!("序列化复制结果失败: {}", e);
+        error!("{}", error_msg);
+        json_error(&error_msg)
+    })
+}
+
 /// JNI函数 - 解析字体文件并提取字体名称映射
 #[no_mangle]
 pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_parseFontsDirectory(
@@ -155,6 +311,573 @@ pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_parseFontsDirectory(
 
     info!("开始解析字体目录: {}", directory_str);
 
-    let result = parse_fonts_and_format(&directory_str);
+    let result = FontParser::parse_fonts_directory(&directory_str);
+    let result = format_font_parse_result_with_options(&result, &output_options());
+    create_java_string(&mut env, &result)
+}
+
+/// JNI函数 - 读取字体中指定 ID 的名称记录，不存在或出错时返回空字符串
+#[no_mangle]
+pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_getFontNameRecord(
+    mut env: JNIEnv,
+    _class: JClass,
+    font_path: JString,
+    name_id: jint,
+) -> jstring {
+    init_logger();
+
+    let font_path_str: String = match env.get_string(&font_path) {
+        Ok(java_str) => java_str.into(),
+        Err(e) => {
+            error!("字体路径参数转换失败: {}", e);
+            return create_java_string(&mut env, "");
+        }
+    };
+
+    let record = match u16::try_from(name_id) {
+        Ok(name_id) => FontParser::name_record(Path::new(&font_path_str), name_id),
+        Err(_) => Err(Error::InvalidArgument(format!("名称 ID {}", name_id))),
+    };
+
+    match record {
+        Ok(value) => create_java_string(&mut env, &value.unwrap_or_default()),
+        Err(e) => {
+            warn!("读取名称记录失败 {}: {}", font_path_str, e);
+            create_java_string(&mut env, "")
+        }
+    }
+}
+
+/// JNI函数 - 查找码位对应的字形名称，不存在或出错时返回空字符串
+#[no_mangle]
+pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_getGlyphName(
+    mut env: JNIEnv,
+    _class: JClass,
+    font_path: JString,
+    codepoint: jint,
+) -> jstring {
+    init_logger();
+
+    let font_path_str: String = match env.get_string(&font_path) {
+        Ok(java_str) => java_str.into(),
+        Err(e) => {
+            error!("字体路径参数转换失败: {}", e);
+            return create_java_string(&mut env, "");
+        }
+    };
+
+    let glyph_name = match u32::try_from(codepoint).ok().and_then(char::from_u32) {
+        Some(ch) => FontParser::glyph_name(Path::new(&font_path_str), ch),
+        None => Err(Error::InvalidArgument(format!("码位 {}", codepoint))),
+    };
+
+    match glyph_name {
+        Ok(name) => create_java_string(&mut env, &name.unwrap_or_default()),
+        Err(e) => {
+            warn!("查找字形名称失败 {}: {}", font_path_str, e);
+            create_java_string(&mut env, "")
+        }
+    }
+}
+
+/// JNI函数 - 渲染字体示例文本
+///
+/// 返回 宽度(4字节) + 高度(4字节) + 灰度像素 的字节数组，出错时返回空数组。
+#[no_mangle]
+pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_renderFontSample(
+    mut env: JNIEnv,
+    _class: JClass,
+    font_path: JString,
+    text: JString,
+    px: jint,
+) -> jbyteArray {
+    init_logger();
+
+    let font_path_str: String = match env.get_string(&font_path) {
+        Ok(java_str) => java_str.into(),
+        Err(e) => {
+            error!("字体路径参数转换失败: {}", e);
+            return create_java_byte_array(&mut env, &[]);
+        }
+    };
+    let text_str: String = match env.get_string(&text) {
+        Ok(java_str) => java_str.into(),
+        Err(e) => {
+            error!("文本参数转换失败: {}", e);
+            return create_java_byte_array(&mut env, &[]);
+        }
+    };
+
+    match FontParser::render_sample(Path::new(&font_path_str), &text_str, px.max(1) as u32) {
+        Ok(bitmap) => create_java_byte_array(&mut env, &bitmap.to_bytes()),
+        Err(e) => {
+            warn!("渲染字体示例失败 {}: {}", font_path_str, e);
+            create_java_byte_array(&mut env, &[])
+        }
+    }
+}
+
+/// JNI函数 - 返回目录中最大的 N 个字体文件（JSON 数组）
+#[no_mangle]
+pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_largestFiles(
+    mut env: JNIEnv,
+    _class: JClass,
+    directory: JString,
+    n: jint,
+) -> jstring {
+    init_logger();
+
+    let directory_str: String = match env.get_string(&directory) {
+        Ok(java_str) => java_str.into(),
+        Err(e) => {
+            let error_msg = format!("目录参数转换失败: {}", e);
+            error!("{}", error_msg);
+            return create_java_string(&mut env, &json_error(&error_msg));
+        }
+    };
+
+    let result = largest_files_json(&directory_str, n.max(0) as usize);
     create_java_string(&mut env, &result)
 }
+
+/// JNI函数 - 返回目录中所有字体文件的路径，每行一个
+#[no_mangle]
+pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_fontPathList(
+    mut env: JNIEnv,
+    _class: JClass,
+    directory: JString,
+) -> jstring {
+    init_logger();
+
+    let directory_str: String = match env.get_string(&directory) {
+        Ok(java_str) => java_str.into(),
+        Err(e) => {
+            let error_msg = format!("目录参数转换失败: {}", e);
+            error!("{}", error_msg);
+            return create_java_string(&mut env, &error_msg);
+        }
+    };
+
+    let font_files = DirectoryScanner::scan_fonts(&directory_str);
+    create_java_string(&mut env, &path_list(&font_files, false))
+}
+
+/// JNI函数 - 检查目录中是否有字体文件，用于决定是否显示扫描入口
+#[no_mangle]
+pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_directoryHasFonts(
+    mut env: JNIEnv,
+    _class: JClass,
+    directory: JString,
+) -> bool {
+    init_logger();
+
+    match env.get_string(&directory) {
+        Ok(java_str) => DirectoryScanner::has_fonts(String::from(java_str)),
+        Err(e) => {
+            error!("目录参数转换失败: {}", e);
+            false
+        }
+    }
+}
+
+/// JNI函数 - 按字体族返回目录中的字体（字重、斜体、路径），供字体选择界面使用
+#[no_mangle]
+pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_fontFamiliesJson(
+    mut env: JNIEnv,
+    _class: JClass,
+    directory: JString,
+) -> jstring {
+    init_logger();
+
+    let directory_str: String = match env.get_string(&directory) {
+        Ok(java_str) => java_str.into(),
+        Err(e) => {
+            let error_msg = format!("目录参数转换失败: {}", e);
+            error!("{}", error_msg);
+            return create_java_string(&mut env, &json_error(&error_msg));
+        }
+    };
+
+    let result = font_families_json(&directory_str);
+    create_java_string(&mut env, &result)
+}
+
+/// 解析目录中的字体并按族名分组为 `{"族名": [{"weight", "italic", "path"}]}`，目录无效时返回错误 JSON
+fn font_families_json(directory: &str) -> String {
+    if !Path::new(directory).is_dir() {
+        let error_msg = format!("目录不存在: {}", directory);
+        warn!("{}", error_msg);
+        return json_error(&error_msg);
+    }
+
+    let result = FontParser::parse_fonts_directory(directory);
+    let families: serde_json::Map<String, serde_json::Value> = group_by_family(&result)
+        .into_iter()
+        .map(|(family, mappings)| {
+            let styles = mappings
+                .into_iter()
+                .map(|mapping| {
+                    serde_json::json!({
+                        "weight": mapping.style_flags.weight_class,
+                        "italic": mapping.is_italic,
+                        "path": mapping.file_path,
+                    })
+                })
+                .collect();
+            (family, serde_json::Value::Array(styles))
+        })
+        .collect();
+    serde_json::Value::Object(families).to_string()
+}
+
+/// JNI函数 - 解析内存中的字体数据（如下载得到的 byte[]），返回 `FontMapping` JSON
+#[no_mangle]
+pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_parseFontBytes(
+    mut env: JNIEnv,
+    _class: JClass,
+    data: JByteArray,
+) -> jstring {
+    init_logger();
+
+    let bytes = match env.convert_byte_array(&data) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let error_msg = format!("字节数组参数转换失败: {}", e);
+            error!("{}", error_msg);
+            return create_java_string(&mut env, &json_error(&error_msg));
+        }
+    };
+
+    let result = parse_font_bytes_json(&bytes);
+    create_java_string(&mut env, &result)
+}
+
+/// 解析字体数据并序列化为 JSON，失败时返回错误 JSON
+fn parse_font_bytes_json(data: &[u8]) -> String {
+    let mapping = match FontParser::parse_bytes(data) {
+        Ok(mapping) => mapping,
+        Err(e) => {
+            warn!("解析内存字体数据失败: {}", e);
+            return json_error(&e.to_string());
+        }
+    };
+    serde_json::to_string(&mapping).unwrap_or_else(|e| json_error(&e.to_string()))
+}
+
+/// 扫描目录并以 JSON 数组返回最大的 N 个字体文件
+fn largest_files_json(directory: &str, n: usize) -> String {
+    let font_files = DirectoryScanner::scan_fonts(directory);
+    let largest: Vec<serde_json::Value> = top_files_by_size(&font_files, n)
+        .into_iter()
+        .map(|file| {
+            serde_json::json!({
+                "name": file.name,
+                "path": file.path.to_string_lossy(),
+                "size": file.size,
+            })
+        })
+        .collect();
+    serde_json::Value::Array(largest).to_string()
+}
+
+/// 以 JSON 包装错误信息
+fn json_error(message: &str) -> String {
+    serde_json::json!({ "error": message }).to_string()
+}
+
+/// JNI函数 - 扫描字体文件并通过回调逐个返回结果
+///
+/// `callback` 需实现 `FontScanCallback` 接口：每个文件调用一次 `onFile(String json)`，
+/// 扫描结束后调用 `onComplete(String statsJson)`。
+#[no_mangle]
+pub extern "C" fn Java_androidx_appcompat_demo_MainActivity_scanFontsWithCallback(
+    mut env: JNIEnv,
+    _class: JClass,
+    directory: JString,
+    callback: JObject,
+) {
+    init_logger();
+
+    let directory_str: String = match env.get_string(&directory) {
+        Ok(java_str) => java_str.into(),
+        Err(e) => {
+            error!("目录参数转换失败: {}", e);
+            return;
+        }
+    };
+
+    info!("流式扫描目录: {}", directory_str);
+
+    let stats = stream_font_files(&directory_str, &mut |json| match call_string_callback(
+        &mut env, &callback, "onFile", json,
+    ) {
+        Ok(()) => true,
+        Err(e) => {
+            error!("回调 onFile 失败: {}", e);
+            false
+        }
+    });
+    let Some(stats) = stats else {
+        return;
+    };
+    if let Err(e) = call_string_callback(&mut env, &callback, "onComplete", &stats.to_string()) {
+        error!("回调 onComplete 失败: {}", e);
+    }
+}
+
+/// 边遍历边将每个字体文件的 JSON 交给 `on_file`，不缓存扫描结果
+///
+/// 返回统计 JSON，`total_size` 随遍历累加，硬链接到同一 inode 的文件只计算一次。
+/// `on_file` 返回 false 时停止扫描并返回 `None`。
+fn stream_font_files(
+    directory: &str,
+    on_file: &mut dyn FnMut(&str) -> bool,
+) -> Option<serde_json::Value> {
+    let mut total_files = 0usize;
+    let mut total_size = 0u64;
+    let mut seen_inodes = HashSet::new();
+
+    for file in DirectoryScanner::scan_iter(directory, &ScanConfig::default()) {
+        // 无法读取的目录已在扫描器中记录，继续处理其余目录
+        let Ok(file) = file else {
+            continue;
+        };
+        total_files += 1;
+        if file.inode.is_none_or(|inode| seen_inodes.insert(inode)) {
+            total_size += file.size;
+        }
+
+        let json = match serde_json::to_string(&file) {
+            Ok(json) => json,
+            Err(e) => {
+                error!("序列化文件信息失败 {}: {}", file.name, e);
+                continue;
+            }
+        };
+        if !on_file(&json) {
+            return None;
+        }
+    }
+
+    Some(serde_json::json!({
+        "directory": directory,
+        "total_files": total_files,
+        "total_size": total_size,
+    }))
+}
+
+/// 调用回调对象上签名为 `(String) -> void` 的方法
+fn call_string_callback(
+    env: &mut JNIEnv,
+    callback: &JObject,
+    method: &str,
+    value: &str,
+) -> jni::errors::Result<()> {
+    let java_string = env.new_string(value)?;
+    let result = env.call_method(
+        callback,
+        method,
+        "(Ljava/lang/String;)V",
+        &[JValue::Object(&java_string)],
+    );
+    // 及时释放局部引用，避免大量文件时局部引用表溢出
+    env.delete_local_ref(java_string)?;
+    result.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_set_log_tag() {
+        assert!(!set_log_tag(String::new()));
+
+        // 日志初始化后设置无效
+        init_logger();
+        assert!(!set_log_tag("MyApp".to_string()));
+        assert_eq!(LOG_TAG.get(), None);
+    }
+
+    #[test]
+    fn test_format_key_values() {
+        let fields = [("path", "/system/fonts"), ("error_kind", "NotFound")];
+        let record = log::Record::builder()
+            .args(format_args!("无法读取目录"))
+            .key_values(&fields)
+            .build();
+        assert_eq!(
+            format_key_values(&record),
+            " path=/system/fonts error_kind=NotFound"
+        );
+
+        let plain = log::Record::builder().args(format_args!("无字段")).build();
+        assert_eq!(format_key_values(&plain), "");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_stream_font_files() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("a.ttf"), [0u8; 10]).unwrap();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        std::fs::write(temp_dir.path().join("sub/b.otf"), [0u8; 20]).unwrap();
+        std::fs::hard_link(
+            temp_dir.path().join("a.ttf"),
+            temp_dir.path().join("link.ttf"),
+        )
+        .unwrap();
+        File::create(temp_dir.path().join("notes.txt")).unwrap();
+        let directory = temp_dir.path().to_str().unwrap();
+
+        let mut names = Vec::new();
+        let stats = stream_font_files(directory, &mut |json| {
+            let value: serde_json::Value = serde_json::from_str(json).unwrap();
+            names.push(value["name"].as_str().unwrap().to_string());
+            true
+        })
+        .unwrap();
+        assert_eq!(names, ["a.ttf", "link.ttf", "b.otf"]);
+        assert_eq!(stats["total_files"], 3);
+        // 硬链接只计算一次
+        assert_eq!(stats["total_size"], 30);
+
+        // 回调失败时立即停止，不再报告统计
+        let mut calls = 0;
+        let stats = stream_font_files(directory, &mut |_| {
+            calls += 1;
+            false
+        });
+        assert!(stats.is_none());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_largest_files_json() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("small.ttf"), [0u8; 10]).unwrap();
+        std::fs::write(temp_dir.path().join("large.ttf"), [0u8; 300]).unwrap();
+        std::fs::write(temp_dir.path().join("medium.ttf"), [0u8; 200]).unwrap();
+
+        let json = largest_files_json(temp_dir.path().to_str().unwrap(), 2);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let names: Vec<&str> = value
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, ["large.ttf", "medium.ttf"]);
+        assert_eq!(value[0]["size"], 300);
+    }
+
+    #[test]
+    fn test_font_families_json() {
+        use crate::test_fonts::TestFont;
+
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(
+            temp_dir.path().join("Demo-Regular.ttf"),
+            TestFont::new("Demo").build(),
+        )
+        .unwrap();
+        let mut bold = TestFont::new("Demo");
+        bold.weight_class = 700;
+        std::fs::write(temp_dir.path().join("Demo-Bold.ttf"), bold.build()).unwrap();
+        std::fs::write(
+            temp_dir.path().join("Other.ttf"),
+            TestFont::new("Other").build(),
+        )
+        .unwrap();
+
+        let json = font_families_json(temp_dir.path().to_str().unwrap());
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let families = value.as_object().unwrap();
+        assert_eq!(families.keys().collect::<Vec<_>>(), ["Demo", "Other"]);
+
+        let mut weights: Vec<u64> = families["Demo"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|style| style["weight"].as_u64().unwrap())
+            .collect();
+        weights.sort();
+        assert_eq!(weights, [400, 700]);
+        assert_eq!(families["Other"][0]["italic"], false);
+        assert!(families["Other"][0]["path"]
+            .as_str()
+            .unwrap()
+            .ends_with("Other.ttf"));
+
+        let missing = temp_dir.path().join("missing");
+        let value: serde_json::Value =
+            serde_json::from_str(&font_families_json(missing.to_str().unwrap())).unwrap();
+        assert!(value["error"].as_str().unwrap().contains("目录不存在"));
+    }
+
+    #[test]
+    fn test_parse_font_bytes_json() {
+        let data = crate::test_fonts::TestFont::new("Demo").build();
+        let value: serde_json::Value = serde_json::from_str(&parse_font_bytes_json(&data)).unwrap();
+        assert_eq!(value["family_name"], "Demo");
+        assert_eq!(value["postscript_name"], "Demo-Regular");
+
+        let value: serde_json::Value =
+            serde_json::from_str(&parse_font_bytes_json(b"garbage")).unwrap();
+        assert!(value["error"]
+            .as_str()
+            .unwrap()
+            .contains("无法识别的字体格式"));
+    }
+
+    #[test]
+    fn test_copy_fonts_json() {
+        let source_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        std::fs::write(source_dir.path().join("a.ttf"), [0u8; 10]).unwrap();
+        std::fs::write(target_dir.path().join("a.ttf"), [0u8; 5]).unwrap();
+        std::fs::write(source_dir.path().join("b.ttf"), [0u8; 20]).unwrap();
+
+        let json = copy_fonts_json(
+            source_dir.path().to_str().unwrap(),
+            target_dir.path().to_str().unwrap(),
+            false,
+        );
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["total_files"], 2);
+        assert_eq!(value["successful_copies"], 1);
+        assert_eq!(value["failed_copies"], 1);
+
+        let failed: Vec<&str> = value["details"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|d| d["success"] == false)
+            .map(|d| d["file_name"].as_str().unwrap())
+            .collect();
+        assert_eq!(failed, ["a.ttf"]);
+    }
+
+    #[test]
+    fn test_load_fonts_info_with_options() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["a.ttf", "b.ttf", "c.ttf"] {
+            File::create(temp_dir.path().join(name)).unwrap();
+        }
+        let directory = temp_dir.path().to_str().unwrap();
+
+        let output = load_fonts_info_with_options(directory, 2, true, true);
+        assert!(output.contains("[DIR] 目录: "));
+        assert!(!output.contains("📁"));
+        assert!(output.contains("... 以及另外 1 个文件"));
+        assert_eq!(output.matches('\n').count(), output.matches("\r\n").count());
+
+        let output = load_fonts_info_with_options(directory, -1, false, false);
+        assert!(output.len() <= MAX_OUTPUT_BYTES);
+        assert!(output.contains("📁 目录: "));
+        assert!(!output.contains("以及另外"));
+        assert!(!output.contains('\r'));
+    }
+}
@@ -1,19 +1,54 @@
 // 模块声明
+mod error;
 mod font_copy;
 mod font_parser;
 mod jni_interface;
 mod scanner;
+#[cfg(test)]
+mod test_fonts;
+mod woff2;
 
 // 重新导出主要功能，保持API兼容性
-pub use font_copy::{copy_font_files, FontCopier};
-pub use font_parser::parse_fonts_and_format;
-pub use scanner::{format_file_size, DirectoryScanner, FileInfo};
+pub use error::{Error, Result};
+pub use font_copy::{
+    compare_trees, copy_font_files, fonts_missing_in_target, format_copy_result_with_options,
+    ConflictPolicy, FontCopier, TreeDiff,
+};
+pub use font_parser::{
+    aggregate_coverage, best_match, dominant_format, find_near_duplicates,
+    format_font_parse_result_with_options, format_font_table, format_font_table_with_options,
+    generate_fonts_xml, group_by_family, group_by_format, parse_font_version,
+    parse_fonts_and_format, sort_font_mappings, sort_font_mappings_by, FontFormat, FontMapping,
+    FontParseOptions, FontParseResult, FontParser, SampleBitmap, StyleFlags,
+};
+pub use scanner::{
+    chunk_files, directory_stats, extensions_present, find_hard_links, format_file_size,
+    format_file_size_with_separator, format_fonts_info, group_by_top_level, path_list,
+    top_files_by_size, total_unique_size, tree_hash, DirectoryScanner, FileChunk, FileInfo,
+    FileType, FormatOptions, IconTheme, LineEnding, ScanConfig, ScanProgress, SkipReason, TreeNode,
+    WalkOrder,
+};
 
+// JNI函数可以在多个 Java 线程中并发调用：日志只初始化一次，
+// 唯一的共享状态是解析缓存文件，其读写由 FontParser 内部的锁串行化。
+//
 // JNI函数自动导出，无需显式重新导出
 // 这些函数在 jni_interface 模块中定义：
 // - Java_androidx_appcompat_demo_MainActivity_loadFontsInfo
+// - Java_androidx_appcompat_demo_MainActivity_loadFontsInfoWithOptions
 // - Java_androidx_appcompat_demo_MainActivity_copyFontFiles
+// - Java_androidx_appcompat_demo_MainActivity_copyFontsJson
 // - Java_androidx_appcompat_demo_MainActivity_parseFontsDirectory
+// - Java_androidx_appcompat_demo_MainActivity_parseFontBytes
+// - Java_androidx_appcompat_demo_MainActivity_scanFontsWithCallback
+// - Java_androidx_appcompat_demo_MainActivity_getFontNameRecord
+// - Java_androidx_appcompat_demo_MainActivity_getGlyphName
+// - Java_androidx_appcompat_demo_MainActivity_renderFontSample
+// - Java_androidx_appcompat_demo_MainActivity_largestFiles
+// - Java_androidx_appcompat_demo_MainActivity_fontPathList
+// - Java_androidx_appcompat_demo_MainActivity_setLogTag
+// - Java_androidx_appcompat_demo_MainActivity_directoryHasFonts
+// - Java_androidx_appcompat_demo_MainActivity_fontFamiliesJson
 
 #[cfg(test)]
 mod tests {
@@ -106,6 +141,402 @@ mod tests {
         assert_eq!(result2.failed_copies, 0);
     }
 
+    #[test]
+    fn test_font_copier_respects_embedding_flags() {
+        let source_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        let mut restricted = test_fonts::TestFont::new("Locked");
+        restricted.fs_type = 0x0002;
+        restricted.write_to(&source_dir.path().join("locked.ttf"));
+        test_fonts::TestFont::new("Open").write_to(&source_dir.path().join("open.ttf"));
+
+        let mut copier = FontCopier::new(false);
+        copier.respect_embedding_flags = true;
+        let result = copier.copy_fonts(source_dir.path(), target_dir.path());
+
+        assert_eq!(result.successful_copies, 1);
+        assert_eq!(result.failed_copies, 0);
+        assert_eq!(result.skipped_copies, 1);
+        let locked = result
+            .details
+            .iter()
+            .find(|d| d.file_name == "locked.ttf")
+            .unwrap();
+        assert_eq!(locked.error.as_deref(), Some("字体禁止嵌入/安装"));
+        assert!(!target_dir.path().join("locked.ttf").exists());
+        assert!(target_dir.path().join("open.ttf").exists());
+    }
+
+    #[test]
+    fn test_font_copier_newest_version_only() {
+        let source_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+
+        for (file_name, revision) in [
+            ("demo-1.ttf", 1.0),
+            ("demo-2.ttf", 2.5),
+            ("demo-0.ttf", 0.5),
+        ] {
+            let mut font = test_fonts::TestFont::new("Demo");
+            font.version = format!("Version {:.3}", revision);
+            font.write_to(&source_dir.path().join(file_name));
+        }
+        test_fonts::TestFont::new("Other").write_to(&source_dir.path().join("other.ttf"));
+
+        let mut copier = FontCopier::new(false);
+        copier.newest_version_only = true;
+        let result = copier.copy_fonts(source_dir.path(), target_dir.path());
+
+        assert_eq!(result.successful_copies, 2);
+        assert_eq!(result.skipped_copies, 2);
+        assert_eq!(result.failed_copies, 0);
+        assert!(target_dir.path().join("demo-2.ttf").exists());
+        assert!(target_dir.path().join("other.ttf").exists());
+        assert!(!target_dir.path().join("demo-1.ttf").exists());
+        assert!(!target_dir.path().join("demo-0.ttf").exists());
+
+        let skipped = result
+            .details
+            .iter()
+            .find(|d| d.file_name == "demo-1.ttf")
+            .unwrap();
+        assert!(!skipped.success);
+        assert!(skipped.skipped);
+        assert!(skipped.error.as_deref().unwrap().contains("demo-2.ttf"));
+    }
+
+    #[test]
+    fn test_font_copier_deduplicate() {
+        let source_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        let source = source_dir.path();
+
+        std::fs::write(source.join("a.ttf"), vec![1u8; 1000]).unwrap();
+        std::fs::write(source.join("a-copy.ttf"), vec![1u8; 1000]).unwrap();
+        std::fs::write(source.join("a-copy2.otf"), vec![1u8; 1000]).unwrap();
+        // 大小相同但内容不同
+        std::fs::write(source.join("b.ttf"), vec![2u8; 1000]).unwrap();
+        std::fs::write(source.join("c.ttf"), vec![1u8; 500]).unwrap();
+
+        let mut copier = FontCopier::new(false);
+        copier.deduplicate = true;
+        let result = copier.copy_fonts(source, target_dir.path());
+
+        assert_eq!(result.successful_copies, 3);
+        assert_eq!(result.files_deduplicated, 2);
+        assert_eq!(result.bytes_deduplicated, 2000);
+        assert_eq!(result.skipped_copies, 2);
+        assert_eq!(result.failed_copies, 0);
+        assert!(target_dir.path().join("a-copy.ttf").exists());
+        assert!(!target_dir.path().join("a.ttf").exists());
+        assert!(!target_dir.path().join("a-copy2.otf").exists());
+
+        let report = font_copy::format_copy_result(&result);
+        // 去重和跳过统计位于统计块内，统计块以空行结束
+        let stats = report.split("\n\n").nth(1).unwrap();
+        assert!(stats.contains("• 失败: 0 个"), "{}", report);
+        assert!(stats.contains("• 跳过: 2 个"), "{}", report);
+        assert!(stats.ends_with("• 去重: 2 个，节省 1.95 KB"), "{}", report);
+    }
+
+    #[test]
+    fn test_compare_trees() {
+        let dir_a = TempDir::new().unwrap();
+        let dir_b = TempDir::new().unwrap();
+        for dir in [dir_a.path(), dir_b.path()] {
+            std::fs::create_dir(dir.join("sub")).unwrap();
+            std::fs::write(dir.join("same.ttf"), b"same").unwrap();
+            std::fs::write(dir.join("sub/nested.otf"), b"nested").unwrap();
+        }
+        std::fs::write(dir_a.path().join("resized.ttf"), b"short").unwrap();
+        std::fs::write(dir_b.path().join("resized.ttf"), b"much longer").unwrap();
+        std::fs::write(dir_a.path().join("edited.ttf"), b"aaaa").unwrap();
+        std::fs::write(dir_b.path().join("edited.ttf"), b"bbbb").unwrap();
+        std::fs::write(dir_a.path().join("a_only.ttf"), b"a").unwrap();
+        std::fs::write(dir_b.path().join("b_only.woff"), b"b").unwrap();
+
+        let a = dir_a.path().to_str().unwrap();
+        let b = dir_b.path().to_str().unwrap();
+        let to_paths = |names: &[&str]| {
+            names
+                .iter()
+                .map(std::path::PathBuf::from)
+                .collect::<Vec<_>>()
+        };
+
+        let diff = compare_trees(a, b, false);
+        assert_eq!(diff.only_in_a, to_paths(&["a_only.ttf"]));
+        assert_eq!(diff.only_in_b, to_paths(&["b_only.woff"]));
+        assert_eq!(diff.differing, to_paths(&["resized.ttf"]));
+        assert_eq!(
+            diff.identical,
+            to_paths(&["edited.ttf", "same.ttf", "sub/nested.otf"])
+        );
+
+        // 按内容比较时能发现大小相同但内容不同的文件
+        let diff = compare_trees(a, b, true);
+        assert_eq!(diff.differing, to_paths(&["edited.ttf", "resized.ttf"]));
+        assert_eq!(diff.identical, to_paths(&["same.ttf", "sub/nested.otf"]));
+    }
+
+    #[test]
+    fn test_concurrent_entry_points() {
+        let font_dir = TempDir::new().unwrap();
+        let cache_dir = TempDir::new().unwrap();
+        for family in ["Alpha", "Beta", "Gamma"] {
+            test_fonts::TestFont::new(family)
+                .write_to(&font_dir.path().join(format!("{}.ttf", family)));
+        }
+        let cache_path = cache_dir.path().join("cache.json");
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let scanned = DirectoryScanner::scan_fonts(font_dir.path()).len();
+                        let parsed = FontParser::parse_fonts_directory(font_dir.path());
+                        let cached = FontParser::parse_fonts_cached(font_dir.path(), &cache_path);
+                        (scanned, parsed.successful_parses, cached.successful_parses)
+                    })
+                })
+                .collect();
+            for handle in handles {
+                assert_eq!(handle.join().unwrap(), (3, 3, 3));
+            }
+        });
+
+        // 并发写入后缓存文件仍然完整
+        let cache = std::fs::read(&cache_path).unwrap();
+        assert!(serde_json::from_slice::<serde_json::Value>(&cache).is_ok());
+        assert_eq!(std::fs::read_dir(cache_dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_copy_to_zip() {
+        use std::io::Read;
+
+        let source_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        let source = source_dir.path();
+        std::fs::write(source.join("a.ttf"), b"top level a").unwrap();
+        std::fs::write(source.join("b.otf"), vec![7u8; 4096]).unwrap();
+        std::fs::create_dir(source.join("sub")).unwrap();
+        std::fs::write(source.join("sub/a.ttf"), b"nested a").unwrap();
+        std::fs::write(source.join("readme.txt"), b"not a font").unwrap();
+
+        let zip_path = target_dir.path().join("fonts.zip");
+        let result = FontCopier::new(false).copy_to_zip(source, &zip_path);
+        assert_eq!(result.successful_copies, 3);
+        assert!(result.errors.is_empty());
+        assert_eq!(
+            result.archive_size,
+            Some(std::fs::metadata(&zip_path).unwrap().len())
+        );
+
+        let mut archive = zip::ZipArchive::new(File::open(&zip_path).unwrap()).unwrap();
+        let mut names: Vec<&str> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(names, ["a (2).ttf", "a.ttf", "b.otf"]);
+
+        let mut read_entry = |name: &str| {
+            let mut content = Vec::new();
+            archive
+                .by_name(name)
+                .unwrap()
+                .read_to_end(&mut content)
+                .unwrap();
+            content
+        };
+        assert_eq!(read_entry("a.ttf"), b"top level a");
+        assert_eq!(read_entry("a (2).ttf"), b"nested a");
+        assert_eq!(read_entry("b.otf"), vec![7u8; 4096]);
+
+        // 不覆盖时保留已有的 ZIP 文件
+        let again = FontCopier::new(false).copy_to_zip(source, &zip_path);
+        assert_eq!(again.successful_copies, 0);
+        assert_eq!(again.errors.len(), 1);
+        assert!(FontCopier::new(true)
+            .copy_to_zip(source, &zip_path)
+            .errors
+            .is_empty());
+    }
+
+    #[test]
+    fn test_font_copier_overwrite_if_newer() {
+        let source_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        let base = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+        let later = base + std::time::Duration::from_secs(100);
+
+        let write = |dir: &std::path::Path, name: &str, content: &str, mtime| {
+            let path = dir.join(name);
+            std::fs::write(&path, content).unwrap();
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(mtime)
+                .unwrap();
+        };
+        write(source_dir.path(), "newer.ttf", "new source", later);
+        write(target_dir.path(), "newer.ttf", "old target", base);
+        write(source_dir.path(), "older.ttf", "old source", base);
+        write(target_dir.path(), "older.ttf", "new target", later);
+        write(source_dir.path(), "same.ttf", "source", base);
+        write(target_dir.path(), "same.ttf", "target", base);
+        write(source_dir.path(), "fresh.ttf", "fresh", base);
+
+        // 只覆盖较旧的目标文件
+        let mut copier = FontCopier::new(true);
+        copier.conflict_policy = ConflictPolicy::OverwriteIfNewer;
+        let result = copier.copy_fonts(source_dir.path(), target_dir.path());
+        assert_eq!(result.successful_copies, 2);
+        assert_eq!(result.skipped_copies, 2);
+        assert_eq!(result.failed_copies, 0);
+
+        let read = |name: &str| std::fs::read_to_string(target_dir.path().join(name)).unwrap();
+        assert_eq!(read("newer.ttf"), "new source");
+        assert_eq!(read("older.ttf"), "new target");
+        assert_eq!(read("same.ttf"), "target");
+        assert_eq!(read("fresh.ttf"), "fresh");
+
+        let older = result
+            .details
+            .iter()
+            .find(|d| d.file_name == "older.ttf")
+            .unwrap();
+        assert!(!older.success);
+        assert!(older.error.as_deref().unwrap().contains("已跳过"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_font_copier_dir_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let source_dir = create_test_directory();
+        let target_root = TempDir::new().unwrap();
+        let root_mode = std::fs::metadata(target_root.path())
+            .unwrap()
+            .permissions()
+            .mode();
+        let target = target_root.path().join("fonts/custom");
+
+        let mut copier = FontCopier::new(false);
+        copier.dir_mode = Some(0o750);
+        let result = copier.copy_fonts(source_dir.path(), &target);
+        assert_eq!(result.successful_copies, 3);
+
+        let mode =
+            |path: &std::path::Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(&target_root.path().join("fonts")), 0o750);
+        assert_eq!(mode(&target), 0o750);
+        // 已存在的目录保持原权限
+        assert_eq!(
+            std::fs::metadata(target_root.path())
+                .unwrap()
+                .permissions()
+                .mode(),
+            root_mode
+        );
+    }
+
+    #[test]
+    fn test_copy_detail_duration() {
+        let source_dir = create_test_directory();
+        let target_dir = TempDir::new().unwrap();
+
+        let result = FontCopier::new(false).copy_fonts(source_dir.path(), target_dir.path());
+        assert_eq!(result.details.len(), 3);
+        // 小文件的复制耗时可能为 0，但不应超过整体耗时
+        for detail in &result.details {
+            assert!(detail.duration_ms <= result.duration_ms);
+        }
+
+        // 未执行复制的条目耗时为 0
+        let skipped = FontCopier::new(false).copy_fonts(source_dir.path(), target_dir.path());
+        assert!(skipped.details.iter().all(|d| d.duration_ms == 0));
+    }
+
+    #[test]
+    fn test_fonts_missing_in_target() {
+        let source_dir = create_test_directory();
+        let target_dir = TempDir::new().unwrap();
+        std::fs::copy(
+            source_dir.path().join("arial.ttf"),
+            target_dir.path().join("arial.ttf"),
+        )
+        .unwrap();
+        // 同内容不同名
+        std::fs::copy(
+            source_dir.path().join("calibri.otf"),
+            target_dir.path().join("calibri-renamed.otf"),
+        )
+        .unwrap();
+        // 同名不同内容
+        std::fs::write(target_dir.path().join("roboto.woff2"), b"other data").unwrap();
+
+        let missing = |by_content: bool| -> Vec<String> {
+            let mut names: Vec<String> = fonts_missing_in_target(
+                source_dir.path().to_str().unwrap(),
+                target_dir.path().to_str().unwrap(),
+                by_content,
+            )
+            .into_iter()
+            .map(|f| f.name)
+            .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(missing(false), ["calibri.otf"]);
+        assert_eq!(missing(true), ["roboto.woff2"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_to_read_only_target() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let source_dir = create_test_directory();
+        let target_dir = TempDir::new().unwrap();
+        std::fs::set_permissions(target_dir.path(), std::fs::Permissions::from_mode(0o555))
+            .unwrap();
+
+        // 以 root 运行时权限位不生效，此时目录实际可写，复制应正常完成
+        let writable = std::fs::write(target_dir.path().join("probe"), b"").is_ok();
+        let result = FontCopier::new(false).copy_fonts(source_dir.path(), target_dir.path());
+        if writable {
+            assert!(result.errors.is_empty());
+            assert_eq!(result.successful_copies, 3);
+        } else {
+            assert_eq!(result.errors.len(), 1);
+            assert!(result.errors[0].contains("目标目录不可写"));
+            assert!(result.details.is_empty());
+        }
+
+        std::fs::set_permissions(target_dir.path(), std::fs::Permissions::from_mode(0o755))
+            .unwrap();
+    }
+
+    /// procfs 中任何用户（包括 root）都无法创建普通文件，探测总会失败
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_copy_to_unwritable_target() {
+        let source_dir = create_test_directory();
+        let result =
+            FontCopier::new(false).copy_fonts(source_dir.path(), std::path::Path::new("/proc"));
+        assert_eq!(result.errors.len(), 1);
+        assert!(
+            result.errors[0].contains("目标目录不可写"),
+            "{:?}",
+            result.errors
+        );
+        assert!(result.details.is_empty());
+        assert_eq!(result.successful_copies, 0);
+    }
+
     #[test]
     fn test_copy_font_files_function() {
         let source_dir = create_test_directory();
@@ -123,6 +554,1076 @@ mod tests {
         assert!(result.contains("✅"));
     }
 
+    #[test]
+    fn test_estimate_entry_count() {
+        let temp_dir = create_test_directory();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        File::create(temp_dir.path().join("sub").join("inner.ttf")).unwrap();
+        File::create(temp_dir.path().join(".hidden")).unwrap();
+
+        // 4个文件 + 1个子目录 + 子目录中的1个文件，隐藏文件不计入
+        assert_eq!(DirectoryScanner::estimate_entry_count(temp_dir.path()), 6);
+    }
+
+    #[test]
+    fn test_list_dir() {
+        let temp_dir = create_test_directory();
+        std::fs::create_dir(temp_dir.path().join("zeta")).unwrap();
+        std::fs::create_dir(temp_dir.path().join("alpha")).unwrap();
+        std::fs::create_dir(temp_dir.path().join(".cache")).unwrap();
+        File::create(temp_dir.path().join("alpha").join("nested.ttf")).unwrap();
+
+        let (directories, files) =
+            DirectoryScanner::list_dir(temp_dir.path(), &ScanConfig::default());
+        let names =
+            |infos: &[FileInfo]| -> Vec<String> { infos.iter().map(|f| f.name.clone()).collect() };
+        assert_eq!(names(&directories), ["alpha", "zeta"]);
+        // 不递归进入子目录，也不包含非字体文件
+        assert_eq!(names(&files), ["arial.ttf", "calibri.otf", "roboto.woff2"]);
+    }
+
+    #[test]
+    fn test_list_dir_with_config() {
+        let temp_dir = create_test_directory();
+        std::fs::create_dir(temp_dir.path().join("node_modules")).unwrap();
+        std::fs::create_dir(temp_dir.path().join("fonts")).unwrap();
+        File::create(temp_dir.path().join(".hidden.ttf")).unwrap();
+        let names =
+            |infos: &[FileInfo]| -> Vec<String> { infos.iter().map(|f| f.name.clone()).collect() };
+
+        let config = ScanConfig {
+            exclude_dirs: vec!["node_modules".to_string()],
+            exclude_patterns: vec!["calibri".to_string()],
+            ..Default::default()
+        };
+        let (directories, files) = DirectoryScanner::list_dir(temp_dir.path(), &config);
+        assert_eq!(names(&directories), ["fonts"]);
+        assert_eq!(names(&files), ["arial.ttf", "roboto.woff2"]);
+
+        let config = ScanConfig {
+            name_prefix: Some("ro".to_string()),
+            ..Default::default()
+        };
+        let (_, files) = DirectoryScanner::list_dir(temp_dir.path(), &config);
+        assert_eq!(names(&files), ["roboto.woff2"]);
+
+        // "fake calibri font data" 为 22 字节，其余字体文件更小
+        let config = ScanConfig {
+            size_ranges: vec![(Some(22), None)],
+            ..Default::default()
+        };
+        let (_, files) = DirectoryScanner::list_dir(temp_dir.path(), &config);
+        assert_eq!(names(&files), ["calibri.otf"]);
+    }
+
+    #[test]
+    fn test_extensions_present() {
+        let temp_dir = create_test_directory();
+        File::create(temp_dir.path().join("UPPER.TTF")).unwrap();
+        File::create(temp_dir.path().join("cjk.otc")).unwrap();
+
+        let files = DirectoryScanner::scan_fonts(temp_dir.path());
+        let extensions: Vec<String> = extensions_present(&files).into_iter().collect();
+        assert_eq!(extensions, ["otc", "otf", "ttf", "woff2"]);
+        assert!(extensions_present(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_directory_stats() {
+        let temp_dir = create_test_directory();
+        let sub_dir = temp_dir.path().join("sub");
+        std::fs::create_dir(&sub_dir).unwrap();
+        std::fs::write(sub_dir.join("extra.ttf"), b"12345").unwrap();
+
+        let files = DirectoryScanner::scan_fonts(temp_dir.path());
+        let stats = directory_stats(&files);
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[temp_dir.path()], (3, 63));
+        assert_eq!(stats[&sub_dir], (1, 5));
+        assert!(directory_stats(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_scan_with_percent() {
+        let temp_dir = create_test_directory();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        File::create(temp_dir.path().join("sub").join("inner.ttf")).unwrap();
+
+        let mut progress = Vec::new();
+        let files = DirectoryScanner::scan_with_percent(temp_dir.path(), |p| progress.push(p));
+
+        assert_eq!(files.len(), 4);
+        // 开始时报告 0.0，6 个条目各报告一次，最后再报告一次完成
+        assert_eq!(progress.len(), 8);
+        assert_eq!(progress[0], 0.0);
+        assert!(progress.windows(2).all(|w| w[0] <= w[1]));
+        assert!(progress.iter().all(|&p| (0.0..=1.0).contains(&p)));
+        assert_eq!(progress.last(), Some(&1.0));
+    }
+
+    #[test]
+    fn test_scan_with_progress() {
+        let temp_dir = create_test_directory();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        File::create(temp_dir.path().join("sub").join("inner.ttf")).unwrap();
+
+        let mut progress = Vec::new();
+        let files =
+            DirectoryScanner::scan_with_progress(temp_dir.path(), |p| progress.push(p.clone()));
+        assert_eq!(files.len(), 4);
+        assert_eq!(progress.len(), 8);
+
+        // 开始时以根目录报告一次
+        assert_eq!(progress[0].done, 0);
+        assert_eq!(progress[0].current_path, temp_dir.path());
+
+        for (i, p) in progress[1..7].iter().enumerate() {
+            assert_eq!(p.done, i + 1);
+            assert_eq!(p.files_seen + p.dirs_seen, p.done);
+            assert_eq!(p.total, 6);
+            assert_eq!(
+                p.eta_ms,
+                p.elapsed_ms * (p.total - p.done) as u64 / p.done as u64
+            );
+        }
+        assert!(progress
+            .windows(2)
+            .all(|w| w[0].elapsed_ms <= w[1].elapsed_ms));
+
+        // 进入子目录前报告该目录，之后才是其中的文件
+        let sub = progress
+            .iter()
+            .position(|p| p.current_path == temp_dir.path().join("sub"))
+            .unwrap();
+        assert_eq!(progress[sub].dirs_seen, 1);
+        assert_eq!(
+            progress[sub + 1].current_path,
+            temp_dir.path().join("sub").join("inner.ttf")
+        );
+
+        let last = progress.last().unwrap();
+        assert_eq!((last.done, last.total, last.eta_ms), (6, 6, 0));
+        assert_eq!((last.files_seen, last.dirs_seen), (5, 1));
+        assert_eq!(last.fraction(), 1.0);
+
+        // 空目录同样以完成结束
+        let empty = TempDir::new().unwrap();
+        let mut progress = Vec::new();
+        DirectoryScanner::scan_with_progress(empty.path(), |p| progress.push(p.clone()));
+        assert_eq!(progress.len(), 2);
+        assert_eq!(progress.last().unwrap().fraction(), 1.0);
+    }
+
+    #[test]
+    fn test_scan_progress_entries_seen() {
+        let temp_dir = create_test_directory();
+        File::create(temp_dir.path().join(".hidden.ttf")).unwrap();
+        std::fs::create_dir(temp_dir.path().join(".git")).unwrap();
+        File::create(temp_dir.path().join(".git").join("packed.ttf")).unwrap();
+
+        let mut last = None;
+        let files =
+            DirectoryScanner::scan_with_progress(temp_dir.path(), |p| last = Some(p.clone()));
+        let last = last.unwrap();
+
+        // 隐藏条目在过滤前计入，跳过的目录不会深入
+        assert_eq!(files.len(), 3);
+        assert_eq!(last.done, 4);
+        assert_eq!(last.entries_seen, 6);
+        assert!(last.entries_seen > files.len());
+    }
+
+    #[test]
+    fn test_scan_progress_dirs_opened() {
+        let temp_dir = create_test_directory();
+        let deeper = temp_dir.path().join("sub").join("deeper");
+        std::fs::create_dir_all(&deeper).unwrap();
+        File::create(deeper.join("inner.ttf")).unwrap();
+        std::fs::create_dir(temp_dir.path().join(".git")).unwrap();
+
+        let mut last = None;
+        DirectoryScanner::scan_with_progress(temp_dir.path(), |p| last = Some(p.clone()));
+        let last = last.unwrap();
+
+        // 根目录、sub 和 sub/deeper 被打开，跳过的隐藏目录不计入
+        assert_eq!(last.dirs_opened, 3);
+        assert_eq!(last.dirs_seen, 2);
+
+        let mut last = None;
+        DirectoryScanner::scan_with_progress(temp_dir.path().join("missing"), |p| {
+            last = Some(p.clone())
+        });
+        assert_eq!(last.unwrap().dirs_opened, 0);
+    }
+
+    #[test]
+    fn test_scan_fonts_with_skips() {
+        let temp_dir = create_test_directory();
+        let root = temp_dir.path();
+        File::create(root.join(".hidden.ttf")).unwrap();
+        File::create(root.join("huge.ttf"))
+            .unwrap()
+            .set_len(51 * 1024 * 1024)
+            .unwrap();
+        std::fs::write(root.join("tiny.otf"), [0u8; 2]).unwrap();
+
+        let config = ScanConfig {
+            size_ranges: vec![(Some(5), None)],
+            ..Default::default()
+        };
+        let (fonts, skipped) = DirectoryScanner::scan_fonts_with_skips(root, &config);
+        assert_eq!(fonts.len(), 3);
+
+        let mut skipped: Vec<(String, SkipReason)> = skipped
+            .into_iter()
+            .map(|(path, reason)| {
+                (
+                    path.file_name().unwrap().to_string_lossy().into_owned(),
+                    reason,
+                )
+            })
+            .collect();
+        skipped.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            skipped,
+            [
+                (".hidden.ttf".to_string(), SkipReason::Hidden),
+                ("huge.ttf".to_string(), SkipReason::TooLarge),
+                ("readme.txt".to_string(), SkipReason::WrongType),
+                ("tiny.otf".to_string(), SkipReason::TooSmall),
+            ]
+        );
+
+        // 名称过滤
+        let config = ScanConfig {
+            name_prefix: Some("arial".to_string()),
+            ..Default::default()
+        };
+        let (fonts, skipped) = DirectoryScanner::scan_fonts_with_skips(root, &config);
+        assert_eq!(fonts.len(), 1);
+        assert!(skipped.contains(&(root.join("calibri.otf"), SkipReason::Filtered)));
+    }
+
+    #[test]
+    fn test_scan_parallel_matches_sequential() {
+        let temp_dir = create_test_directory();
+        let root = temp_dir.path();
+        for dir in ["a", "b/nested", "c"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            File::create(root.join(dir).join("font.ttf")).unwrap();
+            File::create(root.join(dir).join("notes.txt")).unwrap();
+        }
+
+        let sequential = ScanConfig::default();
+        let parallel = ScanConfig {
+            parallel: true,
+            ..Default::default()
+        };
+        let (expected, expected_skipped) =
+            DirectoryScanner::scan_fonts_with_skips(root, &sequential);
+        let (actual, actual_skipped) = DirectoryScanner::scan_fonts_with_skips(root, &parallel);
+        let paths = |files: &[FileInfo]| files.iter().map(|f| f.path.clone()).collect::<Vec<_>>();
+        assert_eq!(paths(&actual), paths(&expected));
+        assert_eq!(actual_skipped, expected_skipped);
+        assert_eq!(actual.len(), 6);
+    }
+
+    #[test]
+    fn test_scan_iter_matches_batch() {
+        let temp_dir = create_test_directory();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("sub/deeper")).unwrap();
+        std::fs::write(root.join("sub/noto.ttf"), b"noto font data").unwrap();
+        std::fs::write(root.join("sub/deeper/tiny.otf"), [0u8; 2]).unwrap();
+        std::fs::create_dir(root.join("skip")).unwrap();
+        File::create(root.join("skip/hidden.ttf")).unwrap();
+
+        let config = ScanConfig {
+            size_ranges: vec![(Some(5), None)],
+            exclude_dirs: vec!["skip".to_string()],
+            ..Default::default()
+        };
+        let batch = DirectoryScanner::scan_fonts_with_config(root, &config);
+        let lazy = DirectoryScanner::scan_iter(root, &config)
+            .collect::<Result<Vec<FileInfo>>>()
+            .unwrap();
+        let paths = |files: &[FileInfo]| files.iter().map(|f| f.path.clone()).collect::<Vec<_>>();
+        assert_eq!(paths(&lazy), paths(&batch));
+        assert_eq!(lazy.len(), 4);
+
+        // 无法读取的目录以错误返回
+        let mut missing = DirectoryScanner::scan_iter(root.join("missing"), &config);
+        assert!(matches!(missing.next(), Some(Err(Error::ReadDir { .. }))));
+        assert!(missing.next().is_none());
+    }
+
+    #[test]
+    fn test_scan_exclude_dirs() {
+        let temp_dir = create_test_directory();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        File::create(root.join("node_modules/pkg/bundled.ttf")).unwrap();
+        File::create(root.join("node_modules/notes.txt")).unwrap();
+        std::fs::create_dir(root.join("cache")).unwrap();
+        File::create(root.join("cache/cached.otf")).unwrap();
+        std::fs::create_dir(root.join("kept")).unwrap();
+        File::create(root.join("kept/noto.ttf")).unwrap();
+
+        let config = ScanConfig {
+            exclude_dirs: vec!["node_modules".to_string(), "cache".to_string()],
+            ..Default::default()
+        };
+        let (fonts, skipped) = DirectoryScanner::scan_fonts_with_skips(root, &config);
+        let mut names: Vec<&str> = fonts.iter().map(|f| f.name.as_str()).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["arial.ttf", "calibri.otf", "noto.ttf", "roboto.woff2"]
+        );
+
+        // 被排除的目录本身记录为 Excluded，其中的条目从未被访问
+        assert!(skipped.contains(&(root.join("node_modules"), SkipReason::Excluded)));
+        assert!(skipped.contains(&(root.join("cache"), SkipReason::Excluded)));
+        assert!(!skipped
+            .iter()
+            .any(|(path, _)| path.starts_with(root.join("node_modules"))
+                && path != &root.join("node_modules")));
+
+        // 正则表达式按完整路径匹配，可同时排除目录和文件
+        let config = ScanConfig {
+            exclude_patterns: vec![
+                "node_modules|cache$".to_string(),
+                r"calibri\.otf$".to_string(),
+            ],
+            ..Default::default()
+        };
+        let (fonts, skipped) = DirectoryScanner::scan_fonts_with_skips(root, &config);
+        let mut names: Vec<&str> = fonts.iter().map(|f| f.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["arial.ttf", "noto.ttf", "roboto.woff2"]);
+        assert!(skipped.contains(&(root.join("calibri.otf"), SkipReason::Excluded)));
+        assert!(skipped.contains(&(root.join("node_modules"), SkipReason::Excluded)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_owner_uid() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_dir = create_test_directory();
+        let uid = std::fs::metadata(temp_dir.path().join("arial.ttf"))
+            .unwrap()
+            .uid();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        File::create(temp_dir.path().join("sub/noto.ttf")).unwrap();
+
+        let config = ScanConfig {
+            owner_uid: Some(uid),
+            ..Default::default()
+        };
+        let fonts = DirectoryScanner::scan_fonts_with_config(temp_dir.path(), &config);
+        // 子目录中的字体同样被保留
+        assert_eq!(fonts.len(), 4);
+        assert!(fonts.iter().all(|f| f.uid == Some(uid)));
+
+        let config = ScanConfig {
+            owner_uid: Some(uid.wrapping_add(1)),
+            ..Default::default()
+        };
+        assert!(DirectoryScanner::scan_fonts_with_config(temp_dir.path(), &config).is_empty());
+    }
+
+    #[test]
+    fn test_path_list() {
+        let temp_dir = create_test_directory();
+        std::fs::create_dir(temp_dir.path().join("sub")).unwrap();
+
+        let (dirs, files) = DirectoryScanner::list_dir(temp_dir.path(), &ScanConfig::default());
+        let entries: Vec<FileInfo> = dirs.into_iter().chain(files).collect();
+
+        let list = path_list(&entries, false);
+        let lines: Vec<&str> = list.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            temp_dir.path().join("arial.ttf").to_string_lossy()
+        );
+        assert!(list.ends_with('\n'));
+
+        let list = path_list(&entries, true);
+        assert_eq!(list.lines().count(), 4);
+        assert!(list.contains(&*temp_dir.path().join("sub").to_string_lossy()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_broken_symlink() {
+        let temp_dir = create_test_directory();
+        std::os::unix::fs::symlink(
+            temp_dir.path().join("missing.ttf"),
+            temp_dir.path().join("dangling.ttf"),
+        )
+        .unwrap();
+
+        let config = ScanConfig {
+            canonicalize_paths: true,
+            ..Default::default()
+        };
+        let fonts = DirectoryScanner::scan_fonts_with_config(temp_dir.path(), &config);
+        assert_eq!(fonts.len(), 4);
+
+        let dangling = fonts.iter().find(|f| f.name == "dangling.ttf").unwrap();
+        assert!(matches!(dangling.file_type, FileType::SymbolicLink));
+        assert!(dangling.broken);
+        assert_eq!(dangling.size, 0);
+        assert!(fonts
+            .iter()
+            .filter(|f| f.name != "dangling.ttf")
+            .all(|f| !f.broken));
+    }
+
+    #[test]
+    fn test_format_options_max_output_bytes() {
+        let options = FormatOptions {
+            max_output_bytes: Some(30),
+            ..Default::default()
+        };
+        // 每个汉字占 3 字节，截断位置落在字符中间时应回退到字符边界
+        let text = "字体文件列表：思源黑体、思源宋体";
+        let output = options.apply(text);
+        assert!(output.len() <= 30);
+        assert_eq!(output, "字体文件列…(truncated)");
+
+        assert_eq!(options.apply("short"), "short");
+
+        // 复制和解析结果的格式化同样遵守输出上限
+        let source_dir = create_test_directory();
+        let target_dir = TempDir::new().unwrap();
+        let copied = FontCopier::new(false).copy_fonts(source_dir.path(), target_dir.path());
+        assert!(font_copy::format_copy_result(&copied).len() > 30);
+        let output = format_copy_result_with_options(&copied, &options);
+        assert!(output.len() <= 30 && output.ends_with("…(truncated)"));
+        let parsed = FontParser::parse_fonts_directory(source_dir.path());
+        for output in [
+            format_font_parse_result_with_options(&parsed, &options),
+            format_font_table_with_options(&parsed, &options),
+        ] {
+            assert!(output.len() <= 30 && output.ends_with("…(truncated)"));
+        }
+        assert_eq!(FormatOptions::default().apply(text), text);
+
+        // 预算小于截断标记的长度时不追加标记，也不超出预算
+        for max_bytes in [0, 1, 5, 13] {
+            let options = FormatOptions {
+                max_output_bytes: Some(max_bytes),
+                ..Default::default()
+            };
+            let output = options.apply(text);
+            assert!(output.len() <= max_bytes);
+            assert!(text.starts_with(&output));
+        }
+        let options = FormatOptions {
+            max_output_bytes: Some(7),
+            ..Default::default()
+        };
+        assert_eq!(options.apply(text), "字体");
+    }
+
+    #[test]
+    fn test_format_fonts_info_max_listed() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["a.ttf", "b.ttf", "c.ttf"] {
+            File::create(temp_dir.path().join(name)).unwrap();
+        }
+        let directory = temp_dir.path().to_str().unwrap();
+
+        let limited = format_fonts_info(directory, Some(1), &FormatOptions::default());
+        assert_eq!(limited.matches("• ").count(), 1);
+        assert!(limited.contains("找到 3 个字体文件"));
+        assert!(limited.contains("... 以及另外 2 个文件"));
+
+        let unlimited = format_fonts_info(directory, None, &FormatOptions::default());
+        assert_eq!(unlimited.matches("• ").count(), 3);
+        assert!(!unlimited.contains("以及另外"));
+    }
+
+    #[test]
+    fn test_format_fonts_info_format_options() {
+        let temp_dir = TempDir::new().unwrap();
+        File::create(temp_dir.path().join("a.ttf")).unwrap();
+        File::create(temp_dir.path().join("📁x.ttf")).unwrap();
+        let directory = temp_dir.path().to_str().unwrap();
+
+        let options = FormatOptions {
+            icons: IconTheme::ascii(),
+            line_ending: LineEnding::CrLf,
+            ..Default::default()
+        };
+        let output = format_fonts_info(directory, None, &options);
+        assert!(output.contains("[DIR] 目录: "));
+        assert!(output.contains("[STAT] 总计: "));
+        assert!(!output.contains("📁 "));
+        // 只替换图标，不改写文件名
+        assert!(output.contains("* 📁x.ttf (TTF)"), "{}", output);
+        assert_eq!(output.matches('\n').count(), output.matches("\r\n").count());
+
+        let default = format_fonts_info(directory, None, &FormatOptions::default());
+        assert!(default.contains("📁 目录: "));
+        assert!(!default.contains('\r'));
+    }
+
+    #[test]
+    fn test_format_fonts_info_custom_icons() {
+        let temp_dir = TempDir::new().unwrap();
+        File::create(temp_dir.path().join("a.ttf")).unwrap();
+        let directory = temp_dir.path().to_str().unwrap();
+
+        let options = FormatOptions {
+            icons: IconTheme {
+                banner: "#".to_string(),
+                directory: "D:".to_string(),
+                fonts: "F:".to_string(),
+                file: "-".to_string(),
+                total: "T:".to_string(),
+                error: "E:".to_string(),
+            },
+            ..Default::default()
+        };
+        let output = format_fonts_info(directory, None, &options);
+        assert!(output.starts_with("# Rust库\nD: 目录: "));
+        assert!(output.contains("F: 找到 1 个字体文件"));
+        assert!(output.contains("- a.ttf (TTF)"));
+        assert!(output.contains("T: 总计: "));
+
+        let empty_dir = TempDir::new().unwrap();
+        let output = format_fonts_info(empty_dir.path().to_str().unwrap(), None, &options);
+        assert!(output.ends_with("E: 未找到字体文件"));
+    }
+
+    #[test]
+    fn test_scan_tree() {
+        let temp_dir = create_test_directory();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("cjk/serif")).unwrap();
+        std::fs::create_dir(root.join("empty")).unwrap();
+        File::create(root.join("cjk/serif/song.otf")).unwrap();
+        File::create(root.join("cjk/hei.ttf")).unwrap();
+
+        let tree = DirectoryScanner::scan_tree(root, &ScanConfig::default()).unwrap();
+        assert!(matches!(tree.info.file_type, FileType::Directory));
+        let names = |node: &TreeNode| -> Vec<String> {
+            node.children.iter().map(|c| c.info.name.clone()).collect()
+        };
+        // 不含字体的目录不出现在树中
+        assert_eq!(
+            names(&tree),
+            ["arial.ttf", "calibri.otf", "cjk", "roboto.woff2"]
+        );
+
+        let cjk = &tree.children[2];
+        assert!(matches!(cjk.info.file_type, FileType::Directory));
+        assert_eq!(names(cjk), ["hei.ttf", "serif"]);
+        assert_eq!(names(&cjk.children[1]), ["song.otf"]);
+        assert!(cjk.children[1].children[0].children.is_empty());
+
+        // 配置同样生效
+        let config = ScanConfig {
+            name_prefix: Some("song".to_string()),
+            ..Default::default()
+        };
+        let tree = DirectoryScanner::scan_tree(root, &config).unwrap();
+        assert_eq!(names(&tree), ["cjk"]);
+        assert_eq!(names(&tree.children[0]), ["serif"]);
+
+        let json = serde_json::to_value(&tree).unwrap();
+        assert_eq!(
+            json["children"][0]["children"][0]["children"][0]["info"]["name"],
+            "song.otf"
+        );
+
+        assert!(DirectoryScanner::scan_tree(root.join("missing"), &config).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_tree_symlinked_root() {
+        let temp_dir = create_test_directory();
+        std::fs::create_dir(temp_dir.path().join("cjk")).unwrap();
+        File::create(temp_dir.path().join("cjk/hei.ttf")).unwrap();
+        let link_dir = TempDir::new().unwrap();
+        let root = link_dir.path().join("fonts");
+        std::os::unix::fs::symlink(temp_dir.path(), &root).unwrap();
+
+        let names = |node: &TreeNode| -> Vec<String> {
+            node.children.iter().map(|c| c.info.name.clone()).collect()
+        };
+        for canonicalize_paths in [false, true] {
+            let config = ScanConfig {
+                canonicalize_paths,
+                ..Default::default()
+            };
+            let tree = DirectoryScanner::scan_tree(&root, &config).unwrap();
+            // 文件仍按相对位置挂在各级目录下，而不是全部挂在根节点下
+            assert_eq!(
+                names(&tree),
+                ["arial.ttf", "calibri.otf", "cjk", "roboto.woff2"]
+            );
+            assert_eq!(names(&tree.children[2]), ["hei.ttf"]);
+        }
+    }
+
+    #[test]
+    fn test_chunk_files() {
+        let temp_dir = create_test_directory();
+        for i in 0..4 {
+            File::create(temp_dir.path().join(format!("extra{}.ttf", i))).unwrap();
+        }
+        let files = DirectoryScanner::scan_fonts(temp_dir.path());
+        assert_eq!(files.len(), 7);
+
+        let chunks = chunk_files(&files, 3);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks
+            .iter()
+            .enumerate()
+            .all(|(i, c)| c.index == i && c.total == 3));
+        assert_eq!(chunks[2].files.len(), 1);
+
+        // 经 JSON 传输后按顺序拼接可还原原始列表
+        let json: Vec<String> = chunks
+            .iter()
+            .map(|c| serde_json::to_string(c).unwrap())
+            .collect();
+        let rebuilt: Vec<std::path::PathBuf> = json
+            .iter()
+            .map(|s| serde_json::from_str::<FileChunk>(s).unwrap())
+            .flat_map(|c| c.files)
+            .map(|f| f.path)
+            .collect();
+        let original: Vec<std::path::PathBuf> = files.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(rebuilt, original);
+
+        let empty = chunk_files(&[], 3);
+        assert_eq!((empty.len(), empty[0].total), (1, 1));
+        assert!(empty[0].files.is_empty());
+        assert_eq!(chunk_files(&files, 0).len(), 7);
+    }
+
+    #[test]
+    fn test_has_fonts() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        std::fs::create_dir_all(root.join("docs/nested")).unwrap();
+        std::fs::write(root.join("docs/readme.txt"), b"text").unwrap();
+        std::fs::write(root.join(".hidden.ttf"), b"hidden").unwrap();
+        assert!(!DirectoryScanner::has_fonts(root));
+
+        std::fs::write(root.join("docs/nested/font.otf"), b"font").unwrap();
+        assert!(DirectoryScanner::has_fonts(root));
+        assert!(!DirectoryScanner::has_fonts(root.join("missing")));
+    }
+
+    #[test]
+    fn test_scan_map() {
+        let temp_dir = create_test_directory();
+        let fonts = DirectoryScanner::scan_map(temp_dir.path());
+
+        assert_eq!(fonts.len(), 3);
+        let arial = &fonts[&temp_dir.path().join("arial.ttf")];
+        assert_eq!(arial.name, "arial.ttf");
+        assert!(!fonts.contains_key(&temp_dir.path().join("readme.txt")));
+    }
+
+    #[test]
+    fn test_scan_walk_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let base = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+
+        // b 最旧, c 居中, a 最新
+        for (name, offset) in [("b.ttf", 0), ("c.ttf", 60), ("a.ttf", 120)] {
+            let file = File::create(temp_dir.path().join(name)).unwrap();
+            file.set_modified(base + std::time::Duration::from_secs(offset))
+                .unwrap();
+        }
+
+        let names = |order: WalkOrder| -> Vec<String> {
+            let config = ScanConfig {
+                walk_order: order,
+                ..Default::default()
+            };
+            DirectoryScanner::scan_fonts_with_config(temp_dir.path(), &config)
+                .into_iter()
+                .map(|f| f.name)
+                .collect()
+        };
+
+        assert_eq!(names(WalkOrder::Name), ["a.ttf", "b.ttf", "c.ttf"]);
+        assert_eq!(names(WalkOrder::MtimeDesc), ["a.ttf", "c.ttf", "b.ttf"]);
+        assert_eq!(names(WalkOrder::MtimeAsc), ["b.ttf", "c.ttf", "a.ttf"]);
+    }
+
+    #[test]
+    fn test_walk_order_stem_then_extension() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["a.ttf", "a-bold.otf", "a.otf", "a-bold.ttf", "b.otf"] {
+            File::create(temp_dir.path().join(name)).unwrap();
+        }
+
+        let names = |order: WalkOrder| -> Vec<String> {
+            let config = ScanConfig {
+                walk_order: order,
+                ..Default::default()
+            };
+            DirectoryScanner::scan_fonts_with_config(temp_dir.path(), &config)
+                .into_iter()
+                .map(|f| f.name)
+                .collect()
+        };
+
+        assert_eq!(
+            names(WalkOrder::Name),
+            ["a-bold.otf", "a-bold.ttf", "a.otf", "a.ttf", "b.otf"]
+        );
+        // 同名文件按扩展名排序，且不受 '-' 与 '.' 字符顺序影响
+        assert_eq!(
+            names(WalkOrder::StemThenExtension),
+            ["a.otf", "a.ttf", "a-bold.otf", "a-bold.ttf", "b.otf"]
+        );
+    }
+
+    #[test]
+    fn test_scan_against_manifest() {
+        let temp_dir = create_test_directory();
+        let manifest: std::collections::HashMap<_, _> =
+            DirectoryScanner::scan_fonts(temp_dir.path())
+                .into_iter()
+                .map(|f| (f.path, (f.size, f.modified.unwrap())))
+                .collect();
+
+        // 修改一个文件，新增一个文件，其余保持不变
+        std::fs::write(
+            temp_dir.path().join("arial.ttf"),
+            b"modified arial font data",
+        )
+        .unwrap();
+        File::create(temp_dir.path().join("new.ttf")).unwrap();
+
+        let changed = DirectoryScanner::scan_against_manifest(temp_dir.path(), &manifest);
+        let mut names: Vec<&str> = changed.iter().map(|f| f.name.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["arial.ttf", "new.ttf"]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_non_utf8_file_name() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let source_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        let raw: &[u8] = b"caf\xe9.ttf";
+        std::fs::write(source_dir.path().join(OsStr::from_bytes(raw)), b"font").unwrap();
+
+        let files = DirectoryScanner::scan_fonts(source_dir.path());
+        assert_eq!(files.len(), 1);
+        assert!(files[0].name_is_lossy);
+        assert_eq!(files[0].raw_name(), raw);
+
+        let result = FontCopier::new(false).copy_fonts(source_dir.path(), target_dir.path());
+        assert_eq!(result.successful_copies, 1);
+        assert!(target_dir.path().join(OsStr::from_bytes(raw)).exists());
+    }
+
+    #[test]
+    fn test_scan_walk_order_ties() {
+        let temp_dir = TempDir::new().unwrap();
+        let mtime = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+
+        for dir in ["b", "a"] {
+            std::fs::create_dir(temp_dir.path().join(dir)).unwrap();
+            for name in ["z.ttf", "m.ttf"] {
+                let file = File::create(temp_dir.path().join(dir).join(name)).unwrap();
+                file.set_modified(mtime).unwrap();
+            }
+        }
+        for dir in ["b", "a"] {
+            File::open(temp_dir.path().join(dir))
+                .unwrap()
+                .set_modified(mtime)
+                .unwrap();
+        }
+
+        for order in [WalkOrder::MtimeDesc, WalkOrder::MtimeAsc] {
+            let config = ScanConfig {
+                walk_order: order,
+                ..Default::default()
+            };
+            let paths: Vec<_> = DirectoryScanner::scan_fonts_with_config(temp_dir.path(), &config)
+                .into_iter()
+                .map(|f| f.path.strip_prefix(temp_dir.path()).unwrap().to_path_buf())
+                .collect();
+            let expected: Vec<std::path::PathBuf> = ["a/m.ttf", "a/z.ttf", "b/m.ttf", "b/z.ttf"]
+                .iter()
+                .map(Into::into)
+                .collect();
+            assert_eq!(paths, expected);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hard_links_counted_once() {
+        let temp_dir = create_test_directory();
+        std::fs::hard_link(
+            temp_dir.path().join("arial.ttf"),
+            temp_dir.path().join("arial-link.ttf"),
+        )
+        .unwrap();
+
+        let files = DirectoryScanner::scan_fonts(temp_dir.path());
+        assert_eq!(files.len(), 4);
+
+        let links = find_hard_links(&files);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].len(), 2);
+        assert!(links[0].iter().all(|f| f.nlink == Some(2)));
+
+        let naive_total: u64 = files.iter().map(|f| f.size).sum();
+        let arial_size = links[0][0].size;
+        assert_eq!(total_unique_size(&files), naive_total - arial_size);
+    }
+
+    #[test]
+    fn test_find_font_directories() {
+        let root = TempDir::new().unwrap();
+        let fonts = root.path().join("fonts");
+        let mixed = root.path().join("mixed");
+        let docs = root.path().join("docs");
+        for dir in [&fonts, &mixed, &docs] {
+            std::fs::create_dir(dir).unwrap();
+        }
+
+        for name in ["a.ttf", "b.otf", "notes.txt"] {
+            File::create(fonts.join(name)).unwrap();
+        }
+        for name in ["a.ttf", "b.txt", "c.txt", "d.txt"] {
+            File::create(mixed.join(name)).unwrap();
+        }
+        File::create(docs.join("readme.md")).unwrap();
+
+        assert_eq!(
+            DirectoryScanner::find_font_directories(root.path(), 0.5),
+            vec![fonts.clone()]
+        );
+        assert_eq!(
+            DirectoryScanner::find_font_directories(root.path(), 0.25),
+            vec![fonts, mixed]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_scan_canonicalize_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let real_dir = temp_dir.path().join("real");
+        std::fs::create_dir(&real_dir).unwrap();
+        File::create(real_dir.join("roboto.ttf")).unwrap();
+        std::os::unix::fs::symlink(
+            real_dir.join("roboto.ttf"),
+            temp_dir.path().join("link.ttf"),
+        )
+        .unwrap();
+        // 指向上级目录的链接不应导致无限递归
+        std::os::unix::fs::symlink(temp_dir.path(), real_dir.join("loop")).unwrap();
+
+        let config = ScanConfig {
+            canonicalize_paths: true,
+            ..Default::default()
+        };
+        let files = DirectoryScanner::scan_fonts_with_config(temp_dir.path(), &config);
+
+        let expected = std::fs::canonicalize(real_dir.join("roboto.ttf")).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, expected);
+        assert_eq!(files[0].name, "roboto.ttf");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_find_symlink_cycles() {
+        let temp_dir = TempDir::new().unwrap();
+        let fonts_dir = temp_dir.path().join("fonts");
+        let other_dir = temp_dir.path().join("other");
+        std::fs::create_dir(&fonts_dir).unwrap();
+        std::fs::create_dir(&other_dir).unwrap();
+        File::create(fonts_dir.join("roboto.ttf")).unwrap();
+
+        std::os::unix::fs::symlink(temp_dir.path(), fonts_dir.join("loop")).unwrap();
+        std::os::unix::fs::symlink(&fonts_dir, fonts_dir.join("self")).unwrap();
+        // 指向兄弟目录或文件的链接不构成循环
+        std::os::unix::fs::symlink(&fonts_dir, other_dir.join("fonts")).unwrap();
+        std::os::unix::fs::symlink(fonts_dir.join("roboto.ttf"), other_dir.join("roboto.ttf"))
+            .unwrap();
+
+        let cycles = DirectoryScanner::find_symlink_cycles(temp_dir.path());
+        assert_eq!(
+            cycles,
+            [
+                fonts_dir.join("loop"),
+                fonts_dir.join("self"),
+                other_dir.join("fonts").join("loop"),
+                other_dir.join("fonts").join("self"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_name_prefix_suffix() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in [
+            "Roboto-Bold.ttf",
+            "Roboto-Regular.ttf",
+            "roboto-light.ttf",
+            "NotoSans-Bold.otf",
+        ] {
+            File::create(temp_dir.path().join(name)).unwrap();
+        }
+
+        let names = |config: ScanConfig| -> Vec<String> {
+            let mut names: Vec<String> =
+                DirectoryScanner::scan_fonts_with_config(temp_dir.path(), &config)
+                    .into_iter()
+                    .map(|f| f.name)
+                    .collect();
+            names.sort();
+            names
+        };
+
+        let prefix_only = ScanConfig {
+            name_prefix: Some("Roboto".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            names(prefix_only.clone()),
+            ["Roboto-Bold.ttf", "Roboto-Regular.ttf"]
+        );
+        assert_eq!(
+            names(ScanConfig {
+                ignore_case: true,
+                ..prefix_only
+            }),
+            ["Roboto-Bold.ttf", "Roboto-Regular.ttf", "roboto-light.ttf"]
+        );
+
+        // 后缀匹配不含扩展名的部分
+        let suffix_only = ScanConfig {
+            name_suffix: Some("-Bold".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(names(suffix_only), ["NotoSans-Bold.otf", "Roboto-Bold.ttf"]);
+
+        let both = ScanConfig {
+            name_prefix: Some("Roboto".to_string()),
+            name_suffix: Some("-Bold".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(names(both), ["Roboto-Bold.ttf"]);
+    }
+
+    #[test]
+    fn test_scan_size_ranges() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("nested")).unwrap();
+        std::fs::write(temp_dir.path().join("small.ttf"), [0u8; 10]).unwrap();
+        std::fs::write(
+            temp_dir.path().join("nested").join("medium.ttf"),
+            [0u8; 100],
+        )
+        .unwrap();
+        std::fs::write(temp_dir.path().join("large.ttf"), [0u8; 1000]).unwrap();
+
+        let config = ScanConfig {
+            size_ranges: vec![(None, Some(10)), (Some(500), None)],
+            ..Default::default()
+        };
+        let mut names: Vec<String> =
+            DirectoryScanner::scan_fonts_with_config(temp_dir.path(), &config)
+                .into_iter()
+                .map(|f| f.name)
+                .collect();
+        names.sort();
+        assert_eq!(names, ["large.ttf", "small.ttf"]);
+
+        // 目录不受大小范围影响，子目录中的文件仍会被扫描
+        let config = ScanConfig {
+            size_ranges: vec![(Some(50), Some(200))],
+            ..Default::default()
+        };
+        let files = DirectoryScanner::scan_fonts_with_config(temp_dir.path(), &config);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "medium.ttf");
+    }
+
+    #[test]
+    fn test_group_by_top_level() {
+        let temp_dir = create_test_directory();
+        let deep = temp_dir.path().join("noto").join("cjk");
+        std::fs::create_dir_all(&deep).unwrap();
+        File::create(deep.join("noto-sc.otf")).unwrap();
+        File::create(temp_dir.path().join("noto").join("noto-sans.ttf")).unwrap();
+
+        let files = DirectoryScanner::scan_fonts(temp_dir.path());
+        let groups = group_by_top_level(temp_dir.path(), &files);
+
+        assert_eq!(groups.keys().collect::<Vec<_>>(), [".", "noto"]);
+        assert_eq!(groups["."].len(), 3);
+        assert_eq!(groups["noto"].len(), 2);
+    }
+
+    #[test]
+    fn test_tree_hash() {
+        let temp_dir = create_test_directory();
+        let root = temp_dir.path();
+
+        let first = tree_hash(root, &DirectoryScanner::scan_fonts(root));
+        let mut reversed = DirectoryScanner::scan_fonts(root);
+        reversed.reverse();
+        assert_eq!(first, tree_hash(root, &reversed));
+        assert_eq!(first.len(), 16);
+
+        std::fs::write(root.join("calibri.otf"), b"changed calibri font data").unwrap();
+        let changed = tree_hash(root, &DirectoryScanner::scan_fonts(root));
+        assert_ne!(first, changed);
+    }
+
+    #[test]
+    fn test_scan_bundle_extensions() {
+        let temp_dir = TempDir::new().unwrap();
+        let bundle = temp_dir.path().join("Fonts.bundle");
+        let collection = temp_dir.path().join("Family.TTC");
+        std::fs::create_dir_all(bundle.join("inner")).unwrap();
+        std::fs::create_dir(&collection).unwrap();
+        std::fs::write(bundle.join("inner").join("a.ttf"), [0u8; 10]).unwrap();
+        std::fs::write(collection.join("regular.ttf"), [0u8; 30]).unwrap();
+        std::fs::write(collection.join("bold.ttf"), [0u8; 20]).unwrap();
+
+        // 默认情况下包目录会被深入遍历
+        assert_eq!(DirectoryScanner::scan_fonts(temp_dir.path()).len(), 3);
+
+        let config = ScanConfig {
+            bundle_extensions: vec!["bundle".to_string(), "ttc".to_string()],
+            ..Default::default()
+        };
+        let files = DirectoryScanner::scan_fonts_with_config(temp_dir.path(), &config);
+
+        // .bundle 不再被深入遍历，且不是字体扩展名；.ttc 包作为单个字体条目报告
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "Family.TTC");
+        assert!(matches!(files[0].file_type, FileType::Bundle));
+        assert_eq!(files[0].size, 50);
+    }
+
     #[test]
     fn test_format_file_size() {
         assert_eq!(format_file_size(0), "0 B");
@@ -131,4 +1632,11 @@ mod tests {
         assert_eq!(format_file_size(1048576), "1.00 MB");
         assert_eq!(format_file_size(1073741824), "1.00 GB");
     }
+
+    #[test]
+    fn test_format_file_size_with_separator() {
+        assert_eq!(format_file_size_with_separator(1536, '.'), "1.50 KB");
+        assert_eq!(format_file_size_with_separator(1536, ','), "1,50 KB");
+        assert_eq!(format_file_size_with_separator(512, ','), "512 B");
+    }
 }
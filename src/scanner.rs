@@ -1,74 +1,1148 @@
-use log::warn;
+use log::{info, warn};
+use rayon::prelude::*;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
 use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::sync::OnceLock;
+use std::time::{Instant, SystemTime};
+
+use crate::error::Error;
 
 /// 文件类型枚举
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FileType {
     Directory,
     RegularFile,
+    /// 按包处理的目录（如 `.bundle`），作为单个条目报告
+    Bundle,
+    /// 跟随符号链接时无法解析的链接（目标不存在）
+    SymbolicLink,
 }
 
 /// 简化的文件信息结构体
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileInfo {
+    /// 显示用文件名，非 UTF-8 字节会被替换为 U+FFFD
     pub name: String,
+    /// 文件名是否经过有损转换；为 true 时应使用 `path` 进行文件操作
+    pub name_is_lossy: bool,
     pub path: PathBuf,
     pub file_type: FileType,
     pub size: u64,
+    /// 修改时间（Unix 时间戳，秒）
+    pub modified: Option<u64>,
+    /// 硬链接数量（仅 Unix）
+    pub nlink: Option<u64>,
+    /// (设备号, inode 号)，用于识别硬链接（仅 Unix）
+    pub inode: Option<(u64, u64)>,
+    /// 所有者 uid（仅 Unix）
+    pub uid: Option<u32>,
+    /// 是否为目标不存在的符号链接
+    #[serde(default)]
+    pub broken: bool,
     pub extension: Option<String>,
 }
 
+/// 目录树节点，子节点顺序与扫描顺序一致
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeNode {
+    pub info: FileInfo,
+    pub children: Vec<TreeNode>,
+}
+
+/// 扫描结果的一个分块，用于通过 JNI 分批传输
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChunk {
+    /// 分块序号，从 0 开始
+    pub index: usize,
+    /// 分块总数
+    pub total: usize,
+    pub files: Vec<FileInfo>,
+}
+
+/// 扫描进度，`total` 为预估的条目总数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanProgress {
+    /// 已处理的条目数
+    pub done: usize,
+    pub total: usize,
+    /// 已用时间（毫秒）
+    pub elapsed_ms: u64,
+    /// 按当前平均速度估算的剩余时间（毫秒），已处理数达到预估总数后为 0
+    pub eta_ms: u64,
+    /// 已处理的文件条目数（含非字体文件）
+    pub files_seen: usize,
+    /// 已遇到的目录数，不含根目录
+    pub dirs_seen: usize,
+    /// 遇到的全部条目数，包括被排除、隐藏或无法读取而跳过的条目，
+    /// 可与结果数量比较以显示“共 N 项中的 M 项”
+    pub entries_seen: usize,
+    /// 实际打开读取的目录数，包括根目录；无法打开的目录不计入
+    pub dirs_opened: usize,
+    /// 最近处理的条目路径，开始时为根目录
+    pub current_path: PathBuf,
+}
+
+impl ScanProgress {
+    /// 完成比例，预估偏低时封顶为 1.0
+    pub fn fraction(&self) -> f32 {
+        if self.done >= self.total {
+            1.0
+        } else {
+            self.done as f32 / self.total as f32
+        }
+    }
+}
+
+/// 目录内条目的遍历顺序
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WalkOrder {
+    /// 按文件名排序
+    #[default]
+    Name,
+    /// 按不含扩展名的文件名排序，同名文件再按扩展名排序，使 `a.otf`、`a.ttf` 相邻
+    StemThenExtension,
+    /// 按修改时间排序，最新的在前
+    MtimeDesc,
+    /// 按修改时间排序，最旧的在前
+    MtimeAsc,
+}
+
+/// 条目未出现在扫描结果中的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SkipReason {
+    /// 以 `.` 开头的隐藏条目
+    Hidden,
+    /// 超过 50MB 上限，或大于 `size_ranges` 允许的大小
+    TooLarge,
+    /// 小于 `size_ranges` 中所有范围的最小值
+    TooSmall,
+    /// 不满足名称前缀/后缀或所有者条件
+    Filtered,
+    /// 不是字体文件，或不是普通文件/目录（如套接字、设备文件）
+    WrongType,
+    /// 无法读取元数据或解析链接
+    Unreadable,
+    /// 列出目录后、读取元数据前已被删除
+    Vanished,
+    /// 名称在 `exclude_dirs` 中的目录，或完整路径匹配 `exclude_patterns` 的条目；目录的整个子树未被遍历
+    Excluded,
+    /// 开启 `skip_special_filesystems` 时跳过的伪文件系统目录（如 `/proc`）
+    SpecialFilesystem,
+    /// 开启 `one_file_system` 时跳过的、与根目录不在同一设备上的目录（如挂载的 SD 卡）
+    OtherFilesystem,
+}
+
+/// 扫描配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScanConfig {
+    /// 每个目录内条目在处理和递归前的排序方式
+    pub walk_order: WalkOrder,
+    /// 解析符号链接并记录规范路径，指向同一目标的条目只保留一个
+    pub canonicalize_paths: bool,
+    /// 文件大小范围 (最小值, 最大值)，闭区间，`None` 表示不限；
+    /// 文件落在任一范围内即保留，为空时不过滤
+    pub size_ranges: Vec<(Option<u64>, Option<u64>)>,
+    /// 扩展名匹配的目录（不含点，忽略大小写）视为包：不再深入遍历，
+    /// 而是以 `FileType::Bundle` 报告，大小为其内容的总和
+    pub bundle_extensions: Vec<String>,
+    /// 文件名（不含扩展名）须以此开头
+    pub name_prefix: Option<String>,
+    /// 文件名（不含扩展名）须以此结尾
+    pub name_suffix: Option<String>,
+    /// 名称前缀/后缀匹配时忽略大小写
+    pub ignore_case: bool,
+    /// 只保留该 uid 拥有的文件，目录不受影响（仅 Unix，其他平台忽略）
+    pub owner_uid: Option<u32>,
+    /// 不进入的目录名称（精确匹配，如 `node_modules`、`.git`），其中的内容不会被遍历
+    pub exclude_dirs: Vec<String>,
+    /// 排除完整路径匹配任一正则表达式的文件和目录，目录不会被进入；无效的表达式记录警告后忽略
+    pub exclude_patterns: Vec<String>,
+    /// 不进入 `/proc`、`/sys`、`/dev` 等伪文件系统；Unix 上还会跳过与它们位于同一设备的目录
+    pub skip_special_filesystems: bool,
+    /// 在 rayon 线程池中并行扫描根目录下的各个子树，结果顺序与顺序扫描一致；
+    /// 进度回调在每个子树完成后才触发
+    pub parallel: bool,
+    /// 只扫描根目录所在的文件系统，不进入挂载在其他设备上的目录（仅 Unix，其他平台忽略）
+    pub one_file_system: bool,
+}
+
+impl FileInfo {
+    /// 原始文件名字节（平台编码），不受有损转换影响
+    pub fn raw_name(&self) -> &[u8] {
+        self.path
+            .file_name()
+            .map(|n| n.as_encoded_bytes())
+            .unwrap_or_default()
+    }
+}
+
+/// 单次扫描中不变的遍历限制，由 `ScanConfig` 预先计算
+#[derive(Default)]
+struct WalkLimits {
+    /// 编译后的 `exclude_patterns`
+    excludes: Vec<Regex>,
+    /// 开启 `one_file_system` 时根目录所在的设备号
+    root_device: Option<u64>,
+}
+
+/// `DirectoryScanner::scan_iter` 的惰性遍历器，栈中每层为一个目录中尚未处理的条目
+struct ScanIter {
+    config: ScanConfig,
+    limits: WalkLimits,
+    visited: HashSet<PathBuf>,
+    stack: Vec<std::vec::IntoIter<fs::DirEntry>>,
+    /// 等待返回的目录读取错误
+    pending_error: Option<Error>,
+}
+
+impl ScanIter {
+    /// 读取目录并压栈，失败时记录错误，在下一次 `next` 时返回
+    fn push_directory(&mut self, path: &Path) {
+        match fs::read_dir(path) {
+            Ok(entries) => {
+                let mut entries: Vec<fs::DirEntry> = entries.flatten().collect();
+                DirectoryScanner::sort_entries(&mut entries, self.config.walk_order);
+                self.stack.push(entries.into_iter());
+            }
+            Err(e) => {
+                warn!(path:? = path, error_kind:? = e.kind(); "无法读取目录: {}", e);
+                self.pending_error = Some(Error::ReadDir {
+                    path: path.to_path_buf(),
+                    source: e,
+                });
+            }
+        }
+    }
+}
+
+impl Iterator for ScanIter {
+    type Item = Result<FileInfo, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(error) = self.pending_error.take() {
+                return Some(Err(error));
+            }
+
+            let Some(entry) = self.stack.last_mut()?.next() else {
+                self.stack.pop();
+                continue;
+            };
+            match DirectoryScanner::classify_entry(
+                &entry,
+                &self.config,
+                &self.limits,
+                &mut self.visited,
+                &mut |_, _| {},
+                None,
+            ) {
+                Some(EntryAction::Report(file_info))
+                    if DirectoryScanner::filter_reason(&file_info, &self.config).is_none() =>
+                {
+                    return Some(Ok(file_info));
+                }
+                Some(EntryAction::Descend(directory)) => self.push_directory(&directory.path),
+                Some(EntryAction::Report(_)) | None => {}
+            }
+        }
+    }
+}
+
+/// 遍历中单个条目的处理方式
+enum EntryAction {
+    /// 作为扫描结果报告（文件或包）
+    Report(FileInfo),
+    /// 进入该目录继续遍历
+    Descend(FileInfo),
+}
+
+/// 遍历过程中报告给 `on_entry` 回调的事件
+#[derive(Debug, Clone, Copy)]
+enum WalkEvent {
+    /// 成功打开一个目录（包括根目录）
+    Opened,
+    /// 遇到一个目录条目，尚未经过任何过滤
+    Seen,
+    /// 条目处理完成，参数为是否为目录
+    Processed(bool),
+}
+
 /// 简化的目录扫描器
 pub struct DirectoryScanner;
 
 impl DirectoryScanner {
     /// 扫描目录中的字体文件
     pub fn scan_fonts<P: AsRef<Path>>(path: P) -> Vec<FileInfo> {
+        Self::scan_fonts_with_config(path, &ScanConfig::default())
+    }
+
+    /// 扫描目录中的字体文件，以路径为键返回，便于按路径直接查找
+    pub fn scan_map<P: AsRef<Path>>(path: P) -> HashMap<PathBuf, FileInfo> {
+        Self::scan_fonts(path)
+            .into_iter()
+            .map(|f| (f.path.clone(), f))
+            .collect()
+    }
+
+    /// 按指定配置扫描，并以目录树形式返回结果，便于界面直接渲染
+    ///
+    /// 只包含直接或间接含有字体的目录。跟随符号链接后位于根目录之外的文件挂在根节点下。
+    /// 开启 `canonicalize_paths` 时根节点同样使用规范路径，与文件路径保持一致。
+    /// 根目录无法读取时返回 `None`。
+    pub fn scan_tree<P: AsRef<Path>>(path: P, config: &ScanConfig) -> Option<TreeNode> {
+        let root = path.as_ref();
+        let metadata = fs::metadata(root).ok()?;
+        let root_path = if config.canonicalize_paths {
+            fs::canonicalize(root).ok()?
+        } else {
+            root.to_path_buf()
+        };
+        let info = Self::file_info_from_metadata(root_path.clone(), root.as_os_str(), &metadata)?;
+        let mut tree = TreeNode {
+            info,
+            children: Vec::new(),
+        };
+
+        for file in Self::scan_fonts_with_config(root, config) {
+            let parents: Vec<OsString> = match file.path.strip_prefix(&root_path) {
+                Ok(relative) => relative
+                    .parent()
+                    .map(|p| p.iter().map(OsStr::to_os_string).collect())
+                    .unwrap_or_default(),
+                Err(_) => Vec::new(),
+            };
+            Self::insert_into_tree(&mut tree, &parents, file);
+        }
+        Some(tree)
+    }
+
+    /// 沿 `parents` 逐级找到（或创建）目录节点，并把文件挂在最后一级下
+    fn insert_into_tree(node: &mut TreeNode, parents: &[OsString], file: FileInfo) {
+        let Some((first, rest)) = parents.split_first() else {
+            node.children.push(TreeNode {
+                info: file,
+                children: Vec::new(),
+            });
+            return;
+        };
+
+        let dir_path = node.info.path.join(first);
+        let index = match node
+            .children
+            .iter()
+            .position(|child| child.info.path == dir_path)
+        {
+            Some(index) => index,
+            None => {
+                let Some(info) = fs::metadata(&dir_path)
+                    .ok()
+                    .and_then(|m| Self::file_info_from_metadata(dir_path, first, &m))
+                else {
+                    // 目录在扫描后消失，直接挂在当前节点下
+                    node.children.push(TreeNode {
+                        info: file,
+                        children: Vec::new(),
+                    });
+                    return;
+                };
+                node.children.push(TreeNode {
+                    info,
+                    children: Vec::new(),
+                });
+                node.children.len() - 1
+            }
+        };
+        Self::insert_into_tree(&mut node.children[index], rest, file);
+    }
+
+    /// 按指定配置扫描目录中的字体文件
+    pub fn scan_fonts_with_config<P: AsRef<Path>>(path: P, config: &ScanConfig) -> Vec<FileInfo> {
+        Self::scan_fonts_with_hook(path.as_ref(), config, &mut |_, _| {}, None)
+    }
+
+    /// 按指定配置扫描，同时返回被跳过的条目及原因，用于排查文件为何没有出现在结果中
+    ///
+    /// 被跳过的隐藏目录只记录目录本身，不会深入其中。
+    pub fn scan_fonts_with_skips<P: AsRef<Path>>(
+        path: P,
+        config: &ScanConfig,
+    ) -> (Vec<FileInfo>, Vec<(PathBuf, SkipReason)>) {
+        let mut skipped = Vec::new();
+        let fonts =
+            Self::scan_fonts_with_hook(path.as_ref(), config, &mut |_, _| {}, Some(&mut skipped));
+        (fonts, skipped)
+    }
+
+    /// 扫描目录中的字体文件，并以 0.0–1.0 的比例报告进度
+    ///
+    /// 先用 `estimate_entry_count` 预估条目总数，开始时报告 0.0，之后每处理一个条目报告一次；
+    /// 预估偏低时进度封顶为 1.0，扫描结束时总会报告 1.0。
+    pub fn scan_with_percent<P, F>(path: P, mut on_progress: F) -> Vec<FileInfo>
+    where
+        P: AsRef<Path>,
+        F: FnMut(f32),
+    {
+        Self::scan_with_progress(path, |progress| on_progress(progress.fraction()))
+    }
+
+    /// 扫描目录中的字体文件，并报告包含计数、当前路径和剩余时间估算的进度
+    ///
+    /// 预估方式与 `scan_with_percent` 相同。开始时以根目录报告一次，之后每处理一个条目
+    /// （包括每个将要进入的目录）报告一次；扫描结束时总会再报告一次，此时 `done` 等于 `total`。
+    pub fn scan_with_progress<P, F>(path: P, mut on_progress: F) -> Vec<FileInfo>
+    where
+        P: AsRef<Path>,
+        F: FnMut(&ScanProgress),
+    {
+        let start = Instant::now();
+        let root = path.as_ref();
+        let total = Self::estimate_entry_count(root).max(1);
+        let mut progress = ScanProgress {
+            done: 0,
+            total,
+            elapsed_ms: 0,
+            eta_ms: 0,
+            files_seen: 0,
+            dirs_seen: 0,
+            entries_seen: 0,
+            dirs_opened: 0,
+            current_path: root.to_path_buf(),
+        };
+        on_progress(&progress);
+
+        let mut on_entry = |entry_path: &Path, event: WalkEvent| {
+            let is_dir = match event {
+                WalkEvent::Opened => {
+                    progress.dirs_opened += 1;
+                    return;
+                }
+                WalkEvent::Seen => {
+                    progress.entries_seen += 1;
+                    return;
+                }
+                WalkEvent::Processed(is_dir) => is_dir,
+            };
+            progress.done += 1;
+            if is_dir {
+                progress.dirs_seen += 1;
+            } else {
+                progress.files_seen += 1;
+            }
+            progress.current_path = entry_path.to_path_buf();
+            progress.elapsed_ms = start.elapsed().as_millis() as u64;
+            let remaining = total.saturating_sub(progress.done) as u64;
+            progress.eta_ms = progress.elapsed_ms * remaining / progress.done as u64;
+            on_progress(&progress);
+        };
+        let files = Self::scan_fonts_with_hook(root, &ScanConfig::default(), &mut on_entry, None);
+
+        progress.total = progress.done;
+        progress.elapsed_ms = start.elapsed().as_millis() as u64;
+        progress.eta_ms = 0;
+        on_progress(&progress);
+        files
+    }
+
+    /// 惰性扫描：遍历目录的同时逐个返回字体文件，适合内存受限时处理大型目录树
+    ///
+    /// 结果及顺序与 `scan_fonts_with_config` 相同（忽略 `parallel`）。
+    /// 无法读取的目录以错误返回，之后继续遍历其余目录。
+    pub fn scan_iter<P: AsRef<Path>>(
+        path: P,
+        config: &ScanConfig,
+    ) -> impl Iterator<Item = Result<FileInfo, Error>> {
+        let path = path.as_ref();
+        let mut iter = ScanIter {
+            config: config.clone(),
+            limits: Self::walk_limits(path, config),
+            visited: Self::initial_visited(path, config),
+            stack: Vec::new(),
+            pending_error: None,
+        };
+        iter.push_directory(path);
+        iter
+    }
+
+    /// 计算单次扫描的遍历限制
+    fn walk_limits(path: &Path, config: &ScanConfig) -> WalkLimits {
+        WalkLimits {
+            excludes: Self::compile_exclude_patterns(&config.exclude_patterns),
+            root_device: config
+                .one_file_system
+                .then(|| Self::device_id(path))
+                .flatten(),
+        }
+    }
+
+    /// 解析符号链接时预先记录根目录，避免指回根目录的链接被重复遍历
+    fn initial_visited(path: &Path, config: &ScanConfig) -> HashSet<PathBuf> {
+        let mut visited = HashSet::new();
+        if config.canonicalize_paths {
+            if let Ok(root) = fs::canonicalize(path) {
+                visited.insert(root);
+            }
+        }
+        visited
+    }
+
+    /// 扫描实现，`on_entry` 以条目路径和遍历事件调用，`skipped` 不为空时记录被跳过的条目
+    fn scan_fonts_with_hook(
+        path: &Path,
+        config: &ScanConfig,
+        on_entry: &mut dyn FnMut(&Path, WalkEvent),
+        mut skipped: Option<&mut Vec<(PathBuf, SkipReason)>>,
+    ) -> Vec<FileInfo> {
+        info!(path:? = path; "开始扫描目录");
+
         let mut files = Vec::new();
-        Self::scan_directory_recursive(&path.as_ref(), &mut files);
+        let mut visited = Self::initial_visited(path, config);
+        let limits = Self::walk_limits(path, config);
+        if config.parallel {
+            Self::scan_directory_parallel(
+                path,
+                config,
+                &limits,
+                &mut files,
+                &mut visited,
+                on_entry,
+                skipped.as_deref_mut(),
+            );
+        } else {
+            Self::scan_directory_recursive(
+                path,
+                config,
+                &limits,
+                &mut files,
+                &mut visited,
+                on_entry,
+                skipped.as_deref_mut(),
+            );
+        }
 
         // 只保留字体文件
-        files
+        let mut fonts = Vec::new();
+        for file in files {
+            match Self::filter_reason(&file, config) {
+                None => fonts.push(file),
+                Some(reason) => {
+                    if let Some(skipped) = skipped.as_deref_mut() {
+                        skipped.push((file.path, reason));
+                    }
+                }
+            }
+        }
+
+        info!(path:? = path, font_count = fonts.len(); "扫描完成");
+        fonts
+    }
+
+    /// 扫描字体文件，只返回相对清单新增或有变化的文件
+    ///
+    /// `manifest` 以路径为键，值为 (大小, 修改时间戳)。
+    pub fn scan_against_manifest<P: AsRef<Path>>(
+        path: P,
+        manifest: &HashMap<PathBuf, (u64, u64)>,
+    ) -> Vec<FileInfo> {
+        Self::scan_fonts(path)
             .into_iter()
-            .filter(|f| matches!(f.file_type, FileType::RegularFile))
-            .filter(|f| Self::is_font_file(f))
+            .filter(|f| match manifest.get(&f.path) {
+                Some(&(size, mtime)) => f.size != size || f.modified != Some(mtime),
+                None => true,
+            })
             .collect()
     }
 
-    /// 递归扫描目录
-    fn scan_directory_recursive(path: &Path, files: &mut Vec<FileInfo>) {
+    /// 非递归地列出目录内容，返回 (子目录, 字体文件)，均按 `config.walk_order` 排序
+    ///
+    /// 与扫描使用相同的规则：跳过隐藏条目、非字体文件和被排除的目录，
+    /// 文件同样按名称、大小和所有者过滤，适用于逐级浏览的文件管理界面。
+    pub fn list_dir<P: AsRef<Path>>(
+        path: P,
+        config: &ScanConfig,
+    ) -> (Vec<FileInfo>, Vec<FileInfo>) {
+        let path = path.as_ref();
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!(path:? = path, error_kind:? = e.kind(); "无法读取目录: {}", e);
+                return (Vec::new(), Vec::new());
+            }
+        };
+
+        let mut entries: Vec<fs::DirEntry> = entries.flatten().collect();
+        Self::sort_entries(&mut entries, config.walk_order);
+
+        let limits = Self::walk_limits(path, config);
+        let mut visited = Self::initial_visited(path, config);
+        let mut directories = Vec::new();
+        let mut files = Vec::new();
+        for entry in &entries {
+            match Self::classify_entry(entry, config, &limits, &mut visited, &mut |_, _| {}, None) {
+                Some(EntryAction::Descend(directory)) => directories.push(directory),
+                Some(EntryAction::Report(file_info))
+                    if Self::filter_reason(&file_info, config).is_none() =>
+                {
+                    files.push(file_info);
+                }
+                Some(EntryAction::Report(_)) | None => {}
+            }
+        }
+        (directories, files)
+    }
+
+    /// 查找形成循环的目录符号链接：跟随链接遍历时，指向当前路径自身或其上级目录的链接
+    ///
+    /// 常见于损坏的字体安装目录，结果按路径排序。
+    pub fn find_symlink_cycles<P: AsRef<Path>>(root: P) -> Vec<PathBuf> {
+        let mut cycles = Vec::new();
+        if let Ok(canonical_root) = fs::canonicalize(root.as_ref()) {
+            let mut ancestors = vec![canonical_root];
+            Self::collect_symlink_cycles(root.as_ref(), &mut ancestors, &mut cycles);
+        }
+        cycles.sort();
+        cycles
+    }
+
+    /// 递归跟随目录和目录链接，`ancestors` 为当前路径上各级目录的规范路径
+    fn collect_symlink_cycles(
+        path: &Path,
+        ancestors: &mut Vec<PathBuf>,
+        cycles: &mut Vec<PathBuf>,
+    ) {
         let entries = match fs::read_dir(path) {
             Ok(entries) => entries,
             Err(e) => {
-                warn!("无法读取目录 {:?}: {}", path, e);
+                warn!(path:? = path, error_kind:? = e.kind(); "无法读取目录: {}", e);
                 return;
             }
         };
 
         for entry in entries.flatten() {
-            if let Some(file_info) = Self::process_entry(&entry) {
-                if matches!(file_info.file_type, FileType::Directory) {
-                    Self::scan_directory_recursive(&file_info.path, files);
-                } else {
-                    files.push(file_info);
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            // 失效的链接无法解析，直接跳过
+            let Ok(target) = fs::canonicalize(entry.path()) else {
+                continue;
+            };
+            if !target.is_dir() {
+                continue;
+            }
+
+            if ancestors.contains(&target) {
+                if entry.file_type().is_ok_and(|t| t.is_symlink()) {
+                    cycles.push(entry.path());
+                }
+                continue;
+            }
+
+            ancestors.push(target);
+            Self::collect_symlink_cycles(&entry.path(), ancestors, cycles);
+            ancestors.pop();
+        }
+    }
+
+    /// 检查目录（含子目录）中是否有字体文件，找到第一个即返回，无需完整扫描
+    ///
+    /// 判断规则与 `scan_fonts` 相同；先检查当前目录中的文件，再进入子目录。
+    pub fn has_fonts<P: AsRef<Path>>(path: P) -> bool {
+        let Ok(entries) = fs::read_dir(path.as_ref()) else {
+            return false;
+        };
+
+        let mut subdirectories = Vec::new();
+        for entry in entries.flatten() {
+            if Self::is_non_font_file(&entry) {
+                continue;
+            }
+            let Ok(file_info) = Self::process_entry(&entry, false) else {
+                continue;
+            };
+            match file_info.file_type {
+                FileType::RegularFile if Self::is_font_file(&file_info) => return true,
+                FileType::Directory => subdirectories.push(file_info.path),
+                _ => {}
+            }
+        }
+        subdirectories.into_iter().any(Self::has_fonts)
+    }
+
+    /// 查找字体目录：直接包含的文件中字体文件占比不低于 `threshold` 的目录
+    pub fn find_font_directories<P: AsRef<Path>>(root: P, threshold: f32) -> Vec<PathBuf> {
+        let mut directories = Vec::new();
+        Self::collect_font_directories(root.as_ref(), threshold, &mut directories);
+        directories.sort();
+        directories
+    }
+
+    /// 检查扫描到的文件是否应被过滤，返回过滤原因
+    fn filter_reason(file: &FileInfo, config: &ScanConfig) -> Option<SkipReason> {
+        if !matches!(
+            file.file_type,
+            FileType::RegularFile | FileType::Bundle | FileType::SymbolicLink
+        ) {
+            return Some(SkipReason::WrongType);
+        }
+        if !Self::matches_name(&file.name, config) {
+            return Some(SkipReason::Filtered);
+        }
+        if let (Some(owner_uid), Some(uid)) = (config.owner_uid, file.uid) {
+            if uid != owner_uid {
+                return Some(SkipReason::Filtered);
+            }
+        }
+        if !Self::is_font_file(file) {
+            return Some(SkipReason::WrongType);
+        }
+        if !Self::matches_size_ranges(file.size, &config.size_ranges) {
+            // 小于所有范围的最小值时为过小，否则（包括落在范围之间）视为过大
+            let below_all = config
+                .size_ranges
+                .iter()
+                .all(|(min, _)| min.is_some_and(|min| file.size < min));
+            return Some(if below_all {
+                SkipReason::TooSmall
+            } else {
+                SkipReason::TooLarge
+            });
+        }
+        None
+    }
+
+    /// 递归统计每个目录的字体文件占比
+    fn collect_font_directories(path: &Path, threshold: f32, directories: &mut Vec<PathBuf>) {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!(path:? = path, error_kind:? = e.kind(); "无法读取目录: {}", e);
+                return;
+            }
+        };
+
+        let mut file_count = 0usize;
+        let mut font_count = 0usize;
+        let mut subdirectories = Vec::new();
+
+        for entry in entries.flatten() {
+            if let Ok(file_info) = Self::process_entry(&entry, false) {
+                match file_info.file_type {
+                    FileType::Directory | FileType::Bundle => subdirectories.push(file_info.path),
+                    FileType::RegularFile => {
+                        file_count += 1;
+                        if Self::is_font_file(&file_info) {
+                            font_count += 1;
+                        }
+                    }
+                    FileType::SymbolicLink => {}
                 }
             }
         }
+
+        if file_count > 0 && font_count as f32 / file_count as f32 >= threshold {
+            directories.push(path.to_path_buf());
+        }
+
+        for subdirectory in subdirectories {
+            Self::collect_font_directories(&subdirectory, threshold, directories);
+        }
+    }
+
+    /// 预估扫描将涉及的条目数量
+    ///
+    /// 只读取目录项类型而不读取元数据，结果为近似值，可作为进度条的分母。
+    pub fn estimate_entry_count<P: AsRef<Path>>(path: P) -> usize {
+        let entries = match fs::read_dir(path.as_ref()) {
+            Ok(entries) => entries,
+            Err(_) => return 0,
+        };
+
+        let mut count = 0;
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+
+            count += 1;
+            if entry.file_type().map(|t| t.is_dir()).unwrap_or(false) {
+                count += Self::estimate_entry_count(entry.path());
+            }
+        }
+        count
+    }
+
+    /// 递归扫描目录
+    ///
+    /// `visited` 记录已处理的规范路径，仅在 `canonicalize_paths` 开启时使用，
+    /// 同时用于避免符号链接造成的目录循环。`on_entry` 在遇到和处理完每个条目时调用
+    /// （参数为条目路径和遍历事件），
+    /// `skipped` 不为空时记录被跳过的条目。
+    fn scan_directory_recursive(
+        path: &Path,
+        config: &ScanConfig,
+        limits: &WalkLimits,
+        files: &mut Vec<FileInfo>,
+        visited: &mut HashSet<PathBuf>,
+        on_entry: &mut dyn FnMut(&Path, WalkEvent),
+        mut skipped: Option<&mut Vec<(PathBuf, SkipReason)>>,
+    ) {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!(path:? = path, error_kind:? = e.kind(); "无法读取目录: {}", e);
+                return;
+            }
+        };
+        on_entry(path, WalkEvent::Opened);
+
+        let mut entries: Vec<fs::DirEntry> = entries.flatten().collect();
+        Self::sort_entries(&mut entries, config.walk_order);
+
+        for entry in entries {
+            Self::scan_entry(
+                &entry,
+                config,
+                limits,
+                files,
+                visited,
+                on_entry,
+                skipped.as_deref_mut(),
+            );
+        }
+    }
+
+    /// 处理单个目录条目：文件加入 `files`，目录继续递归
+    fn scan_entry(
+        entry: &fs::DirEntry,
+        config: &ScanConfig,
+        limits: &WalkLimits,
+        files: &mut Vec<FileInfo>,
+        visited: &mut HashSet<PathBuf>,
+        on_entry: &mut dyn FnMut(&Path, WalkEvent),
+        mut skipped: Option<&mut Vec<(PathBuf, SkipReason)>>,
+    ) {
+        match Self::classify_entry(
+            entry,
+            config,
+            limits,
+            visited,
+            on_entry,
+            skipped.as_deref_mut(),
+        ) {
+            Some(EntryAction::Report(file_info)) => files.push(file_info),
+            Some(EntryAction::Descend(directory)) => Self::scan_directory_recursive(
+                &directory.path,
+                config,
+                limits,
+                files,
+                visited,
+                on_entry,
+                skipped,
+            ),
+            None => {}
+        }
+    }
+
+    /// 判断目录条目应作为结果报告、继续深入还是跳过（跳过时返回 `None`）
+    fn classify_entry(
+        entry: &fs::DirEntry,
+        config: &ScanConfig,
+        limits: &WalkLimits,
+        visited: &mut HashSet<PathBuf>,
+        on_entry: &mut dyn FnMut(&Path, WalkEvent),
+        mut skipped: Option<&mut Vec<(PathBuf, SkipReason)>>,
+    ) -> Option<EntryAction> {
+        on_entry(&entry.path(), WalkEvent::Seen);
+        if Self::is_excluded_path(&entry.path(), &limits.excludes) {
+            if let Some(skipped) = skipped.as_deref_mut() {
+                skipped.push((entry.path(), SkipReason::Excluded));
+            }
+            return None;
+        }
+
+        // 非字体的普通文件最终都会被过滤，提前跳过以省去读取元数据
+        if !config.canonicalize_paths && Self::is_non_font_file(entry) {
+            on_entry(&entry.path(), WalkEvent::Processed(false));
+            if let Some(skipped) = skipped.as_deref_mut() {
+                skipped.push((entry.path(), SkipReason::WrongType));
+            }
+            return None;
+        }
+
+        let file_info = match Self::process_entry(entry, config.canonicalize_paths) {
+            Ok(file_info) => file_info,
+            Err(reason) => {
+                if let Some(skipped) = skipped.as_deref_mut() {
+                    skipped.push((entry.path(), reason));
+                }
+                return None;
+            }
+        };
+
+        on_entry(
+            &file_info.path,
+            WalkEvent::Processed(matches!(file_info.file_type, FileType::Directory)),
+        );
+        if config.canonicalize_paths && !visited.insert(file_info.path.clone()) {
+            return None;
+        }
+
+        if !matches!(file_info.file_type, FileType::Directory) {
+            return Some(EntryAction::Report(file_info));
+        }
+
+        let reason = if config.exclude_dirs.contains(&file_info.name) {
+            Some(SkipReason::Excluded)
+        } else if config.skip_special_filesystems && Self::is_special_filesystem(&file_info.path) {
+            info!(path:? = file_info.path; "跳过伪文件系统目录");
+            Some(SkipReason::SpecialFilesystem)
+        } else if Self::is_other_device(&file_info.path, limits.root_device) {
+            info!(path:? = file_info.path; "跳过其他文件系统上的目录");
+            Some(SkipReason::OtherFilesystem)
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            if let Some(skipped) = skipped {
+                skipped.push((file_info.path, reason));
+            }
+            return None;
+        }
+
+        if Self::is_bundle(&file_info, &config.bundle_extensions) {
+            Some(EntryAction::Report(FileInfo {
+                file_type: FileType::Bundle,
+                size: Self::directory_size(&file_info.path),
+                ..file_info
+            }))
+        } else {
+            Some(EntryAction::Descend(file_info))
+        }
+    }
+
+    /// 并行扫描根目录：每个条目（含其子树）作为一个任务在 rayon 线程池中处理，
+    /// 结果按条目顺序合并，与顺序扫描一致
+    fn scan_directory_parallel(
+        path: &Path,
+        config: &ScanConfig,
+        limits: &WalkLimits,
+        files: &mut Vec<FileInfo>,
+        visited: &mut HashSet<PathBuf>,
+        on_entry: &mut dyn FnMut(&Path, WalkEvent),
+        mut skipped: Option<&mut Vec<(PathBuf, SkipReason)>>,
+    ) {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                warn!(path:? = path, error_kind:? = e.kind(); "无法读取目录: {}", e);
+                return;
+            }
+        };
+        on_entry(path, WalkEvent::Opened);
+
+        let mut entries: Vec<fs::DirEntry> = entries.flatten().collect();
+        Self::sort_entries(&mut entries, config.walk_order);
+
+        let collect_skips = skipped.is_some();
+        let subtrees: Vec<_> = entries
+            .par_iter()
+            .map(|entry| {
+                let mut subtree_files = Vec::new();
+                let mut subtree_visited = visited.clone();
+                let mut subtree_skipped = Vec::new();
+                let mut seen_entries = Vec::new();
+                Self::scan_entry(
+                    entry,
+                    config,
+                    limits,
+                    &mut subtree_files,
+                    &mut subtree_visited,
+                    &mut |path, event| seen_entries.push((path.to_path_buf(), event)),
+                    collect_skips.then_some(&mut subtree_skipped),
+                );
+                (subtree_files, subtree_skipped, seen_entries)
+            })
+            .collect();
+
+        for (subtree_files, subtree_skipped, seen_entries) in subtrees {
+            for (path, event) in seen_entries {
+                on_entry(&path, event);
+            }
+            if let Some(skipped) = skipped.as_deref_mut() {
+                skipped.extend(subtree_skipped);
+            }
+            // 各子树各自去重，合并时再去掉跨子树指向同一目标的条目
+            files.extend(
+                subtree_files
+                    .into_iter()
+                    .filter(|file| !config.canonicalize_paths || visited.insert(file.path.clone())),
+            );
+        }
+    }
+
+    /// 路径所在的设备号（Unix 上为 `st_dev`），其他平台返回 `None`
+    fn device_id(path: &Path) -> Option<u64> {
+        #[cfg(unix)]
+        {
+            fs::metadata(path).ok().map(|metadata| metadata.dev())
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = path;
+            None
+        }
+    }
+
+    /// 目录是否位于与根目录不同的设备上，`root_device` 为 `None` 时不检查
+    fn is_other_device(path: &Path, root_device: Option<u64>) -> bool {
+        root_device.is_some_and(|root| Self::device_id(path).is_some_and(|device| device != root))
+    }
+
+    /// 是否为伪文件系统目录，扫描进入这些目录可能挂起或不断出错
+    fn is_special_filesystem(path: &Path) -> bool {
+        const SPECIAL_ROOTS: [&str; 3] = ["/proc", "/sys", "/dev"];
+
+        #[cfg(unix)]
+        {
+            static DEVICES: OnceLock<Vec<u64>> = OnceLock::new();
+            let roots = SPECIAL_ROOTS.map(Path::new);
+            let devices = DEVICES.get_or_init(|| Self::special_devices(&roots, Path::new("/")));
+            Self::is_on_special_device(path, &roots, devices)
+        }
+        #[cfg(not(unix))]
+        {
+            SPECIAL_ROOTS.iter().any(|root| path == Path::new(root))
+        }
+    }
+
+    /// 记录各挂载点所在的设备号
+    ///
+    /// 与 `rootfs` 位于同一设备的挂载点并未单独挂载（如容器中未挂载 /sys 时只是普通目录），
+    /// 不予记录，否则根文件系统上的所有目录都会被跳过。
+    #[cfg(unix)]
+    fn special_devices(roots: &[&Path], rootfs: &Path) -> Vec<u64> {
+        let rootfs_device = Self::device_id(rootfs);
+        roots
+            .iter()
+            .filter_map(|root| Self::device_id(root))
+            .filter(|&device| Some(device) != rootfs_device)
+            .collect()
+    }
+
+    /// 路径为给定的挂载点之一，或与这些挂载点位于同一设备（挂载在别处的伪文件系统）
+    #[cfg(unix)]
+    fn is_on_special_device(path: &Path, roots: &[&Path], devices: &[u64]) -> bool {
+        roots.contains(&path)
+            || fs::metadata(path).is_ok_and(|metadata| devices.contains(&metadata.dev()))
+    }
+
+    /// 编译排除路径的正则表达式，无效的表达式记录警告后跳过
+    fn compile_exclude_patterns(patterns: &[String]) -> Vec<Regex> {
+        patterns
+            .iter()
+            .filter_map(|pattern| match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    warn!(pattern = pattern.as_str(); "无效的排除表达式，已忽略: {}", e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// 完整路径是否匹配任一排除表达式
+    fn is_excluded_path(path: &Path, excludes: &[Regex]) -> bool {
+        if excludes.is_empty() {
+            return false;
+        }
+        let path = path.to_string_lossy();
+        excludes.iter().any(|regex| regex.is_match(&path))
+    }
+
+    /// 按遍历顺序排序目录条目
+    fn sort_entries(entries: &mut [fs::DirEntry], order: WalkOrder) {
+        let mtime = |entry: &fs::DirEntry| {
+            entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+        };
+
+        // 修改时间相同的条目按完整路径排序，保证结果在不同运行和平台间一致
+        match order {
+            WalkOrder::Name => entries.sort_by_key(|e| e.file_name()),
+            WalkOrder::StemThenExtension => entries.sort_by_cached_key(|e| {
+                let path = e.path();
+                (
+                    path.file_stem().map(|s| s.to_os_string()),
+                    path.extension().map(|s| s.to_os_string()),
+                )
+            }),
+            WalkOrder::MtimeDesc => {
+                entries.sort_by_cached_key(|e| (std::cmp::Reverse(mtime(e)), e.path()))
+            }
+            WalkOrder::MtimeAsc => entries.sort_by_cached_key(|e| (mtime(e), e.path())),
+        }
     }
 
     /// 处理单个目录条目
-    fn process_entry(entry: &fs::DirEntry) -> Option<FileInfo> {
-        let path = entry.path();
-        let name = entry.file_name().to_string_lossy().to_string();
+    ///
+    /// `canonicalize` 为 true 时跟随符号链接，并以解析后的规范路径作为文件路径和名称。
+    /// 条目被跳过时返回原因。
+    fn process_entry(entry: &fs::DirEntry, canonicalize: bool) -> Result<FileInfo, SkipReason> {
+        let entry_name = entry.file_name();
+
+        // 当前目录和上级目录条目永远不应出现在结果中，即使将来允许隐藏文件
+        if Self::is_dot_entry(&entry_name.to_string_lossy()) {
+            return Err(SkipReason::Hidden);
+        }
 
         // 跳过隐藏文件
-        if name.starts_with('.') {
-            return None;
+        if entry_name.to_string_lossy().starts_with('.') {
+            return Err(SkipReason::Hidden);
+        }
+
+        let unreadable = |e| Self::metadata_skip_reason(&entry.path(), e);
+        let (path, metadata) = if canonicalize {
+            if Self::is_broken_symlink(&entry.path()) {
+                return Ok(Self::broken_symlink_info(entry));
+            }
+            let path = fs::canonicalize(entry.path()).map_err(unreadable)?;
+            let metadata = fs::metadata(&path).map_err(unreadable)?;
+            (path, metadata)
+        } else {
+            (entry.path(), entry.metadata().map_err(unreadable)?)
+        };
+
+        let file_info = Self::file_info_from_metadata(path, &entry_name, &metadata)
+            .ok_or(SkipReason::WrongType)?;
+
+        // 跳过过大的文件（50MB限制）
+        if file_info.size > 50 * 1024 * 1024 {
+            return Err(SkipReason::TooLarge);
         }
 
-        let metadata = entry.metadata().ok()?;
+        Ok(file_info)
+    }
+
+    /// 根据元数据生成文件信息，不是普通文件或目录时返回 `None`
+    ///
+    /// `fallback_name` 用于路径没有文件名部分的情况（如 `/`）。
+    fn file_info_from_metadata(
+        path: PathBuf,
+        fallback_name: &OsStr,
+        metadata: &fs::Metadata,
+    ) -> Option<FileInfo> {
+        let os_name = path.file_name().unwrap_or(fallback_name);
+        let name = os_name.to_string_lossy().to_string();
+        let name_is_lossy = os_name.to_str().is_none();
 
         let file_type = if metadata.is_dir() {
             FileType::Directory
@@ -79,11 +1153,20 @@ impl DirectoryScanner {
         };
 
         let size = metadata.len();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs());
 
-        // 跳过过大的文件（50MB限制）
-        if size > 50 * 1024 * 1024 {
-            return None;
-        }
+        #[cfg(unix)]
+        let (nlink, inode, uid) = (
+            Some(metadata.nlink()),
+            Some((metadata.dev(), metadata.ino())),
+            Some(metadata.uid()),
+        );
+        #[cfg(not(unix))]
+        let (nlink, inode, uid) = (None, None, None);
 
         let extension = path
             .extension()
@@ -92,25 +1175,167 @@ impl DirectoryScanner {
 
         Some(FileInfo {
             name,
+            name_is_lossy,
             path,
             file_type,
             size,
+            modified,
+            nlink,
+            inode,
+            uid,
+            broken: false,
             extension,
         })
     }
 
+    /// 将读取元数据时的错误转换为跳过原因
+    ///
+    /// 在活动的文件系统上，条目可能在 `read_dir` 之后被删除，这种情况不视为错误。
+    fn metadata_skip_reason(path: &Path, e: std::io::Error) -> SkipReason {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            return SkipReason::Vanished;
+        }
+        warn!(path:? = path, error_kind:? = e.kind(); "无法读取文件元数据: {}", e);
+        SkipReason::Unreadable
+    }
+
+    /// 链接本身存在但目标无法访问
+    fn is_broken_symlink(path: &Path) -> bool {
+        fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+            && fs::metadata(path).is_err()
+    }
+
+    /// 为失效的符号链接生成条目，大小记为 0
+    fn broken_symlink_info(entry: &fs::DirEntry) -> FileInfo {
+        let path = entry.path();
+        let entry_name = entry.file_name();
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+
+        FileInfo {
+            name: entry_name.to_string_lossy().to_string(),
+            name_is_lossy: entry_name.to_str().is_none(),
+            path,
+            file_type: FileType::SymbolicLink,
+            size: 0,
+            modified: None,
+            nlink: None,
+            inode: None,
+            uid: None,
+            broken: true,
+            extension,
+        }
+    }
+
+    /// 检查目录是否应按包处理
+    fn is_bundle(file_info: &FileInfo, bundle_extensions: &[String]) -> bool {
+        file_info.extension.as_ref().is_some_and(|ext| {
+            bundle_extensions
+                .iter()
+                .any(|bundle_ext| bundle_ext.eq_ignore_ascii_case(ext))
+        })
+    }
+
+    /// 递归计算目录内所有文件的总大小（不跟随符号链接）
+    fn directory_size(path: &Path) -> u64 {
+        let Ok(entries) = fs::read_dir(path) else {
+            return 0;
+        };
+
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let file_type = entry.file_type().ok()?;
+                if file_type.is_dir() {
+                    Some(Self::directory_size(&entry.path()))
+                } else if file_type.is_file() {
+                    entry.metadata().ok().map(|m| m.len())
+                } else {
+                    None
+                }
+            })
+            .sum()
+    }
+
+    /// 检查文件大小是否落在任一范围内
+    fn matches_size_ranges(size: u64, ranges: &[(Option<u64>, Option<u64>)]) -> bool {
+        ranges.is_empty()
+            || ranges.iter().any(|&(min, max)| {
+                min.is_none_or(|min| size >= min) && max.is_none_or(|max| size <= max)
+            })
+    }
+
+    /// 检查文件名（不含扩展名）是否满足前缀和后缀条件
+    fn matches_name(name: &str, config: &ScanConfig) -> bool {
+        let stem = Path::new(name)
+            .file_stem()
+            .map(|s| s.to_string_lossy())
+            .unwrap_or_default();
+        let normalize = |s: &str| {
+            if config.ignore_case {
+                s.to_lowercase()
+            } else {
+                s.to_string()
+            }
+        };
+        let stem = normalize(&stem);
+
+        config
+            .name_prefix
+            .as_deref()
+            .is_none_or(|prefix| stem.starts_with(&normalize(prefix)))
+            && config
+                .name_suffix
+                .as_deref()
+                .is_none_or(|suffix| stem.ends_with(&normalize(suffix)))
+    }
+
+    /// 检查是否为 `.` 或 `..` 条目
+    fn is_dot_entry(name: &str) -> bool {
+        name == "." || name == ".."
+    }
+
     /// 检查是否为字体文件
     fn is_font_file(file_info: &FileInfo) -> bool {
-        if let Some(ext) = &file_info.extension {
-            matches!(ext.as_str(), "ttf" | "otf" | "woff" | "woff2" | "eot" | "ttc")
-        } else {
-            false
+        file_info
+            .extension
+            .as_deref()
+            .is_some_and(Self::is_font_extension)
+    }
+
+    /// 检查小写扩展名是否为字体扩展名
+    fn is_font_extension(ext: &str) -> bool {
+        matches!(
+            ext,
+            "ttf" | "otf" | "woff" | "woff2" | "eot" | "ttc" | "otc"
+        )
+    }
+
+    /// 不读取元数据判断条目是否为非字体的普通文件（不包括隐藏文件）
+    ///
+    /// `DirEntry::file_type()` 在 Linux/Android 等平台上直接使用目录项中的类型，无需 stat。
+    fn is_non_font_file(entry: &fs::DirEntry) -> bool {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            return false;
         }
+        entry.file_type().is_ok_and(|t| t.is_file())
+            && !entry
+                .path()
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| Self::is_font_extension(&ext.to_lowercase()))
     }
 }
 
 /// 格式化文件大小
 pub fn format_file_size(size: u64) -> String {
+    format_file_size_with_separator(size, '.')
+}
+
+/// 使用指定的小数分隔符格式化文件大小，如德语、法语等地区使用 `,`
+pub fn format_file_size_with_separator(size: u64, decimal_separator: char) -> String {
     const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
     let mut size = size as f64;
     let mut unit_index = 0;
@@ -123,6 +1348,630 @@ pub fn format_file_size(size: u64) -> String {
     if unit_index == 0 {
         format!("{} {}", size as u64, UNITS[unit_index])
     } else {
-        format!("{:.2} {}", size, UNITS[unit_index])
+        let number =
+            format!("{:.2}", size).replace('.', decimal_separator.encode_utf8(&mut [0; 4]));
+        format!("{} {}", number, UNITS[unit_index])
+    }
+}
+
+/// 格式化输出使用的换行符
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+/// 格式化输出使用的图标，可替换为应用自己的图标约定或字形码
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconTheme {
+    pub banner: String,
+    pub directory: String,
+    pub fonts: String,
+    pub file: String,
+    pub total: String,
+    pub error: String,
+}
+
+impl Default for IconTheme {
+    fn default() -> Self {
+        Self {
+            banner: "🗡🗡🗡".to_string(),
+            directory: "📁".to_string(),
+            fonts: "🔤".to_string(),
+            file: "•".to_string(),
+            total: "📊".to_string(),
+            error: "❌".to_string(),
+        }
+    }
+}
+
+impl IconTheme {
+    /// 只使用 ASCII 标记的图标，适配无法正常显示 emoji 的 TextView
+    pub fn ascii() -> Self {
+        Self {
+            banner: "===".to_string(),
+            directory: "[DIR]".to_string(),
+            fonts: "[FONT]".to_string(),
+            file: "*".to_string(),
+            total: "[STAT]".to_string(),
+            error: "[ERR]".to_string(),
+        }
+    }
+}
+
+/// 格式化输出选项，默认保持原样输出
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    pub icons: IconTheme,
+    pub line_ending: LineEnding,
+    /// 输出的最大字节数（含截断标记），超出时在字符边界处截断，避免返回过大的 jstring
+    pub max_output_bytes: Option<usize>,
+}
+
+impl FormatOptions {
+    /// 输出被截断时追加的标记
+    const TRUNCATED_MARKER: &'static str = "…(truncated)";
+
+    /// 按选项转换已格式化的文本
+    pub fn apply(&self, text: &str) -> String {
+        let mut output = text.to_string();
+        if self.line_ending == LineEnding::CrLf {
+            output = output.replace('\n', "\r\n");
+        }
+        if let Some(max_bytes) = self.max_output_bytes {
+            if output.len() > max_bytes {
+                // 预算容纳不下截断标记时只保留正文前缀，保证结果不超过 max_bytes
+                let marker = if max_bytes >= Self::TRUNCATED_MARKER.len() {
+                    Self::TRUNCATED_MARKER
+                } else {
+                    ""
+                };
+                let mut cut = max_bytes - marker.len();
+                while !output.is_char_boundary(cut) {
+                    cut -= 1;
+                }
+                output.truncate(cut);
+                output.push_str(marker);
+            }
+        }
+        output
+    }
+}
+
+/// 扫描目录并格式化字体信息摘要
+///
+/// `max_listed` 限制列出的文件数量，超出部分以汇总行代替；统计信息始终基于全部文件。
+/// `options` 控制换行符和标题样式。
+pub fn format_fonts_info(
+    directory: &str,
+    max_listed: Option<usize>,
+    options: &FormatOptions,
+) -> String {
+    let font_files = DirectoryScanner::scan_fonts(directory);
+    let icons = &options.icons;
+
+    if font_files.is_empty() {
+        return options.apply(&format!(
+            "{} 目录: {}\n{} 未找到字体文件",
+            icons.directory, directory, icons.error
+        ));
+    }
+
+    let mut output = String::new();
+    output.push_str(&format!("{} Rust库\n", icons.banner));
+    output.push_str(&format!("{} 目录: {}\n", icons.directory, directory));
+    output.push_str(&format!(
+        "{} 找到 {} 个字体文件:\n\n",
+        icons.fonts,
+        font_files.len()
+    ));
+
+    let total_size = total_unique_size(&font_files);
+
+    let listed = max_listed.unwrap_or(font_files.len());
+    for file in font_files.iter().take(listed) {
+        let ext = file.extension.as_deref().unwrap_or("unknown");
+        output.push_str(&format!(
+            "{} {} ({}) - {}\n",
+            icons.file,
+            file.name,
+            ext.to_uppercase(),
+            format_file_size(file.size)
+        ));
+    }
+
+    if font_files.len() > listed {
+        output.push_str(&format!(
+            "... 以及另外 {} 个文件\n",
+            font_files.len() - listed
+        ));
+    }
+
+    output.push_str(&format!(
+        "\n{} 总计: {}",
+        icons.total,
+        format_file_size(total_size)
+    ));
+    options.apply(&output)
+}
+
+/// 按扫描根目录下的第一级子目录对文件分组
+///
+/// 直接位于根目录下的文件归入 `"."`，不在根目录下的文件被忽略。
+pub fn group_by_top_level<'a>(
+    root: &Path,
+    files: &'a [FileInfo],
+) -> BTreeMap<String, Vec<&'a FileInfo>> {
+    let mut groups: BTreeMap<String, Vec<&FileInfo>> = BTreeMap::new();
+    for file in files {
+        let Ok(relative) = file.path.strip_prefix(root) else {
+            continue;
+        };
+
+        let mut components = relative.components();
+        let first = components.next();
+        let key = match (first, components.next()) {
+            (Some(dir), Some(_)) => dir.as_os_str().to_string_lossy().to_string(),
+            _ => ".".to_string(),
+        };
+        groups.entry(key).or_default().push(file);
+    }
+    groups
+}
+
+/// 计算整棵目录树的哈希，用于快速判断扫描结果是否有变化
+///
+/// 对按相对路径排序的 (相对路径, 大小, 修改时间) 依次做 FNV-1a 64 位哈希，
+/// 与文件在扫描结果中的顺序无关，不同运行之间结果稳定。
+pub fn tree_hash(root: &Path, files: &[FileInfo]) -> String {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut entries: Vec<(&Path, u64, Option<u64>)> = files
+        .iter()
+        .map(|f| {
+            let relative = f.path.strip_prefix(root).unwrap_or(&f.path);
+            (relative, f.size, f.modified)
+        })
+        .collect();
+    entries.sort();
+
+    let mut hash = FNV_OFFSET;
+    let mut feed = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    };
+    for (relative, size, modified) in entries {
+        feed(relative.as_os_str().as_encoded_bytes());
+        feed(&[0]);
+        feed(&size.to_le_bytes());
+        feed(&modified.unwrap_or(0).to_le_bytes());
+    }
+    format!("{:016x}", hash)
+}
+
+/// 计算文件内容的哈希值，读取失败时返回 `None`
+///
+/// 仅用于同一进程内比较文件内容，哈希值不应持久化。
+pub(crate) fn content_hash(path: &Path) -> Option<u64> {
+    let data = fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+/// 逐字节比较两个文件的内容，任一文件读取失败时返回 `false`
+///
+/// `content_hash` 相同只说明内容可能相同，判定为重复前需用此函数确认。
+pub(crate) fn same_contents(a: &Path, b: &Path) -> bool {
+    matches!((fs::read(a), fs::read(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+/// 将扫描结果按每块最多 `chunk_size` 个文件拆分，接收方按 `index` 顺序拼接即可还原
+///
+/// 结果为空时仍返回一个空分块，使接收方能确认传输结束；`chunk_size` 为 0 时按 1 处理。
+pub fn chunk_files(files: &[FileInfo], chunk_size: usize) -> Vec<FileChunk> {
+    if files.is_empty() {
+        return vec![FileChunk {
+            index: 0,
+            total: 1,
+            files: Vec::new(),
+        }];
+    }
+
+    let chunks: Vec<&[FileInfo]> = files.chunks(chunk_size.max(1)).collect();
+    let total = chunks.len();
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, chunk)| FileChunk {
+            index,
+            total,
+            files: chunk.to_vec(),
+        })
+        .collect()
+}
+
+/// 每行输出一个路径，便于交给 shell 工具处理
+///
+/// `include_directories` 为 false 时跳过目录条目。
+pub fn path_list(files: &[FileInfo], include_directories: bool) -> String {
+    let mut output = String::new();
+    for file in files {
+        if !include_directories && matches!(file.file_type, FileType::Directory) {
+            continue;
+        }
+        output.push_str(&file.path.to_string_lossy());
+        output.push('\n');
+    }
+    output
+}
+
+/// 列出扫描结果中出现的扩展名（小写、不含点），供界面只提供能匹配到文件的筛选项
+pub fn extensions_present(files: &[FileInfo]) -> BTreeSet<String> {
+    files
+        .iter()
+        .filter_map(|file| file.extension.as_deref())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// 按所在目录汇总直接子文件的 (文件数, 总字节数)，目录条目本身不计入
+pub fn directory_stats(files: &[FileInfo]) -> HashMap<PathBuf, (usize, u64)> {
+    let mut stats: HashMap<PathBuf, (usize, u64)> = HashMap::new();
+    for file in files {
+        if matches!(file.file_type, FileType::Directory) {
+            continue;
+        }
+        if let Some(parent) = file.path.parent() {
+            let entry = stats.entry(parent.to_path_buf()).or_default();
+            entry.0 += 1;
+            entry.1 += file.size;
+        }
+    }
+    stats
+}
+
+/// 返回最大的 `n` 个文件，大小相同时按路径排序
+pub fn top_files_by_size(files: &[FileInfo], n: usize) -> Vec<&FileInfo> {
+    let mut sorted: Vec<&FileInfo> = files.iter().collect();
+    sorted.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+    sorted.truncate(n);
+    sorted
+}
+
+/// 找出指向同一 inode 的硬链接文件组（每组至少两个条目）
+pub fn find_hard_links(files: &[FileInfo]) -> Vec<Vec<&FileInfo>> {
+    let mut groups: HashMap<(u64, u64), Vec<&FileInfo>> = HashMap::new();
+    for file in files {
+        if let Some(inode) = file.inode {
+            groups.entry(inode).or_default().push(file);
+        }
+    }
+
+    let mut links: Vec<Vec<&FileInfo>> = groups.into_values().filter(|g| g.len() > 1).collect();
+    links.sort_by(|a, b| a[0].path.cmp(&b[0].path));
+    links
+}
+
+/// 计算文件总大小，硬链接到同一 inode 的文件只计算一次
+pub fn total_unique_size(files: &[FileInfo]) -> u64 {
+    let mut seen = HashSet::new();
+    files
+        .iter()
+        .filter(|f| f.inode.is_none_or(|inode| seen.insert(inode)))
+        .map(|f| f.size)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_matches_size_ranges() {
+        let ranges = [
+            (Some(10), Some(20)),
+            (Some(15), Some(30)),
+            (Some(100), None),
+        ];
+        let matches = |size| DirectoryScanner::matches_size_ranges(size, &ranges);
+
+        assert!(!matches(5));
+        assert!(matches(10));
+        assert!(matches(25)); // 只落在第二个范围
+        assert!(!matches(50));
+        assert!(matches(100));
+        assert!(matches(u64::MAX));
+
+        assert!(DirectoryScanner::matches_size_ranges(0, &[]));
+        assert!(DirectoryScanner::matches_size_ranges(7, &[(None, Some(7))]));
+    }
+
+    #[test]
+    fn test_metadata_skip_reason() {
+        use std::io::{Error, ErrorKind};
+
+        let path = Path::new("/fonts/gone.ttf");
+        assert_eq!(
+            DirectoryScanner::metadata_skip_reason(path, Error::from(ErrorKind::NotFound)),
+            SkipReason::Vanished
+        );
+        assert_eq!(
+            DirectoryScanner::metadata_skip_reason(path, Error::from(ErrorKind::PermissionDenied)),
+            SkipReason::Unreadable
+        );
+
+        // 列出目录后删除文件，模拟扫描过程中文件消失
+        let temp_dir = TempDir::new().unwrap();
+        File::create(temp_dir.path().join("gone.ttf")).unwrap();
+        let entry = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        fs::remove_file(entry.path()).unwrap();
+        for canonicalize in [false, true] {
+            assert_eq!(
+                DirectoryScanner::process_entry(&entry, canonicalize).unwrap_err(),
+                SkipReason::Vanished
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_non_font_file() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["a.ttf", "b.OTF", "c.txt", "noext", ".hidden.txt"] {
+            File::create(temp_dir.path().join(name)).unwrap();
+        }
+        fs::create_dir(temp_dir.path().join("dir.txt")).unwrap();
+
+        let mut non_fonts: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .flatten()
+            .filter(DirectoryScanner::is_non_font_file)
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect();
+        non_fonts.sort();
+        assert_eq!(non_fonts, ["c.txt", "noext"]);
+    }
+
+    /// 在列出目录之后删除文件：若扫描读取了它的元数据，只能得到 `Vanished`，
+    /// 得到 `WrongType` 说明非字体文件没有被 stat
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn test_non_font_files_are_not_stat() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        for name in ["a.ttf", "b.txt", "c.txt", "d.ttf"] {
+            File::create(root.join(name)).unwrap();
+        }
+
+        let mut removed = false;
+        let mut on_entry = |_: &Path, event: WalkEvent| {
+            if matches!(event, WalkEvent::Processed(_)) && !removed {
+                for name in ["b.txt", "c.txt", "d.ttf"] {
+                    fs::remove_file(root.join(name)).unwrap();
+                }
+                removed = true;
+            }
+        };
+        let mut skipped = Vec::new();
+        let files = DirectoryScanner::scan_fonts_with_hook(
+            root,
+            &ScanConfig::default(),
+            &mut on_entry,
+            Some(&mut skipped),
+        );
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(
+            skipped,
+            [
+                (root.join("b.txt"), SkipReason::WrongType),
+                (root.join("c.txt"), SkipReason::WrongType),
+                // 字体文件仍需读取元数据，删除后被识别为已消失
+                (root.join("d.ttf"), SkipReason::Vanished),
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_on_special_device() {
+        let special = TempDir::new().unwrap();
+        let inside = special.path().join("self");
+        fs::create_dir(&inside).unwrap();
+        let device = fs::metadata(special.path()).unwrap().dev();
+
+        // 配置的挂载点本身及同一设备上的目录都被视为伪文件系统
+        let roots = [Path::new("/nonexistent-special-root")];
+        assert!(DirectoryScanner::is_on_special_device(
+            Path::new("/nonexistent-special-root"),
+            &roots,
+            &[]
+        ));
+        assert!(DirectoryScanner::is_on_special_device(
+            &inside,
+            &roots,
+            &[device]
+        ));
+        assert!(!DirectoryScanner::is_on_special_device(
+            &inside,
+            &roots,
+            &[]
+        ));
+        assert!(!DirectoryScanner::is_on_special_device(
+            &inside,
+            &roots,
+            &[device.wrapping_add(1)]
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_special_devices_ignores_unmounted_roots() {
+        // 模拟容器中未单独挂载的 /sys：它只是根文件系统上的普通目录
+        let rootfs = TempDir::new().unwrap();
+        let unmounted = rootfs.path().join("sys");
+        let other = rootfs.path().join("data");
+        fs::create_dir(&unmounted).unwrap();
+        fs::create_dir(&other).unwrap();
+
+        let roots = [unmounted.as_path()];
+        let devices = DirectoryScanner::special_devices(&roots, rootfs.path());
+        assert!(devices.is_empty());
+        assert!(!DirectoryScanner::is_on_special_device(
+            &other, &roots, &devices
+        ));
+        // 挂载点本身仍按路径跳过
+        assert!(DirectoryScanner::is_on_special_device(
+            &unmounted, &roots, &devices
+        ));
+
+        // 根文件系统上的目录不会被视为伪文件系统
+        assert!(!DirectoryScanner::is_special_filesystem(rootfs.path()));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_skip_special_filesystems() {
+        assert!(DirectoryScanner::is_special_filesystem(Path::new("/proc")));
+
+        // 指向 /proc/self 的链接解析为 /proc/<pid>，按设备号识别并跳过，不会进入遍历
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        File::create(temp_dir.path().join("sub/a.ttf")).unwrap();
+        std::os::unix::fs::symlink("/proc/self", temp_dir.path().join("proc_link")).unwrap();
+
+        let config = ScanConfig {
+            canonicalize_paths: true,
+            skip_special_filesystems: true,
+            ..Default::default()
+        };
+        let (files, skipped) = DirectoryScanner::scan_fonts_with_skips(temp_dir.path(), &config);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "a.ttf");
+        assert_eq!(
+            skipped,
+            [(
+                fs::canonicalize("/proc/self").unwrap(),
+                SkipReason::SpecialFilesystem
+            )]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_one_file_system() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("mounted")).unwrap();
+        File::create(temp_dir.path().join("mounted/a.ttf")).unwrap();
+        File::create(temp_dir.path().join("b.ttf")).unwrap();
+
+        // 同一设备上的目录照常进入
+        let config = ScanConfig {
+            one_file_system: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            DirectoryScanner::scan_fonts_with_config(temp_dir.path(), &config).len(),
+            2
+        );
+
+        // 模拟根目录位于另一设备：子目录被视为挂载点而跳过，文件不受影响
+        let device = DirectoryScanner::device_id(temp_dir.path()).unwrap();
+        let limits = WalkLimits {
+            root_device: Some(device.wrapping_add(1)),
+            ..Default::default()
+        };
+        let mut files = Vec::new();
+        let mut skipped = Vec::new();
+        DirectoryScanner::scan_directory_recursive(
+            temp_dir.path(),
+            &config,
+            &limits,
+            &mut files,
+            &mut HashSet::new(),
+            &mut |_, _| {},
+            Some(&mut skipped),
+        );
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "b.ttf");
+        assert_eq!(
+            skipped,
+            [(temp_dir.path().join("mounted"), SkipReason::OtherFilesystem)]
+        );
+    }
+
+    #[test]
+    fn test_same_contents() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("a.ttf"), b"font data").unwrap();
+        fs::write(root.join("b.ttf"), b"font data").unwrap();
+        fs::write(root.join("c.ttf"), b"font date").unwrap();
+
+        assert!(same_contents(&root.join("a.ttf"), &root.join("b.ttf")));
+        // 大小相同但内容不同
+        assert!(!same_contents(&root.join("a.ttf"), &root.join("c.ttf")));
+        assert!(!same_contents(
+            &root.join("a.ttf"),
+            &root.join("missing.ttf")
+        ));
+    }
+
+    #[test]
+    fn test_compile_exclude_patterns() {
+        let excludes = DirectoryScanner::compile_exclude_patterns(&[
+            r"/node_modules$".to_string(),
+            "(unclosed".to_string(),
+            r"\.git/".to_string(),
+        ]);
+        assert_eq!(excludes.len(), 2);
+        assert!(DirectoryScanner::is_excluded_path(
+            Path::new("/sdcard/app/node_modules"),
+            &excludes
+        ));
+        assert!(DirectoryScanner::is_excluded_path(
+            Path::new("/sdcard/repo/.git/fonts/a.ttf"),
+            &excludes
+        ));
+        assert!(!DirectoryScanner::is_excluded_path(
+            Path::new("/sdcard/node_modules_backup/a.ttf"),
+            &excludes
+        ));
+        assert!(!DirectoryScanner::is_excluded_path(Path::new("/any"), &[]));
+    }
+
+    #[test]
+    fn test_dot_entries_never_reported() {
+        assert!(DirectoryScanner::is_dot_entry("."));
+        assert!(DirectoryScanner::is_dot_entry(".."));
+        assert!(!DirectoryScanner::is_dot_entry(".hidden"));
+        assert!(!DirectoryScanner::is_dot_entry("..."));
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("sub")).unwrap();
+        File::create(temp_dir.path().join("sub").join("a.ttf")).unwrap();
+
+        let mut files = Vec::new();
+        DirectoryScanner::scan_directory_recursive(
+            temp_dir.path(),
+            &ScanConfig::default(),
+            &WalkLimits::default(),
+            &mut files,
+            &mut HashSet::new(),
+            &mut |_, _| {},
+            None,
+        );
+        assert_eq!(files.len(), 1);
+        assert!(files.iter().all(|f| f.name != "." && f.name != ".."));
     }
 }
@@ -0,0 +1,332 @@
+//! 测试用的最小字体构造器
+//!
+//! 按需拼装一个只包含必要表的 TrueType 文件，避免在仓库中存放二进制字体。
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// 测试字体描述
+#[derive(Debug, Clone)]
+pub struct TestFont {
+    pub family: String,
+    pub subfamily: String,
+    pub postscript_name: String,
+    pub version: String,
+    pub revision: f64,
+    pub weight_class: u16,
+    pub width_class: u16,
+    pub fs_type: u16,
+    pub fs_selection: u16,
+    /// OS/2 表版本，0–1 不含 OBLIQUE 等新标志位
+    pub os2_version: u16,
+    /// 每个字符对应一个方块字形；为空时不生成 glyf/loca/cmap/hmtx 表
+    pub chars: Vec<char>,
+    /// 生成 post 表（格式 2），字符字形命名为 uniXXXX
+    pub glyph_names: bool,
+    /// GSUB 表中的特性标签；为空时不生成 GSUB 表
+    pub features: Vec<[u8; 4]>,
+    /// 模拟位图字体：不生成 glyf/loca，改为生成不含 strike 的 sbix 表
+    pub bitmap_only: bool,
+}
+
+impl TestFont {
+    pub fn new(family: &str) -> Self {
+        Self {
+            family: family.to_string(),
+            subfamily: "Regular".to_string(),
+            postscript_name: format!("{}-Regular", family.replace(' ', "")),
+            version: "Version 1.000".to_string(),
+            revision: 1.0,
+            weight_class: 400,
+            width_class: 5,
+            fs_type: 0,
+            fs_selection: 0x0040,
+            os2_version: 0,
+            chars: Vec::new(),
+            glyph_names: false,
+            features: Vec::new(),
+            bitmap_only: false,
+        }
+    }
+
+    /// 写入到指定路径
+    pub fn write_to(&self, path: &Path) {
+        fs::write(path, self.build()).unwrap();
+    }
+
+    /// 生成字体文件字节
+    pub fn build(&self) -> Vec<u8> {
+        let mut tables: BTreeMap<[u8; 4], Vec<u8>> = BTreeMap::new();
+        tables.insert(*b"head", self.head());
+        tables.insert(*b"hhea", self.hhea());
+        tables.insert(*b"maxp", self.maxp());
+        tables.insert(*b"OS/2", self.os2());
+        tables.insert(*b"name", self.name());
+        if self.bitmap_only {
+            tables.insert(*b"sbix", self.sbix());
+        }
+        if !self.chars.is_empty() {
+            if !self.bitmap_only {
+                let (glyf, loca) = self.glyf_and_loca();
+                tables.insert(*b"glyf", glyf);
+                tables.insert(*b"loca", loca);
+            }
+            tables.insert(*b"cmap", self.cmap());
+            tables.insert(*b"hmtx", self.hmtx());
+            if self.glyph_names {
+                tables.insert(*b"post", self.post());
+            }
+        }
+        if !self.features.is_empty() {
+            tables.insert(*b"GSUB", self.gsub());
+        }
+        build_sfnt(&tables)
+    }
+
+    fn head(&self) -> Vec<u8> {
+        let mut t = Vec::new();
+        push_u32(&mut t, 0x0001_0000); // version
+        push_u32(&mut t, (self.revision * 65536.0) as u32); // fontRevision
+        push_u32(&mut t, 0); // checkSumAdjustment
+        push_u32(&mut t, 0x5F0F_3CF5); // magicNumber
+        push_u16(&mut t, 0); // flags
+        push_u16(&mut t, 1000); // unitsPerEm
+        t.extend_from_slice(&[0; 16]); // created, modified
+        t.extend_from_slice(&[0; 8]); // xMin, yMin, xMax, yMax
+        push_u16(&mut t, 0); // macStyle
+        push_u16(&mut t, 8); // lowestRecPPEM
+        push_u16(&mut t, 2); // fontDirectionHint
+        push_u16(&mut t, 0); // indexToLocFormat
+        push_u16(&mut t, 0); // glyphDataFormat
+        t
+    }
+
+    fn hhea(&self) -> Vec<u8> {
+        let mut t = Vec::new();
+        push_u32(&mut t, 0x0001_0000); // version
+        push_u16(&mut t, 800); // ascender
+        push_u16(&mut t, (-200i16) as u16); // descender
+        t.extend_from_slice(&[0; 26]);
+        push_u16(&mut t, self.num_glyphs()); // numberOfHMetrics
+        t
+    }
+
+    fn maxp(&self) -> Vec<u8> {
+        let mut t = Vec::new();
+        push_u32(&mut t, 0x0000_5000); // version 0.5
+        push_u16(&mut t, self.num_glyphs());
+        t
+    }
+
+    /// 字形 0 为空的 .notdef，其后每个字符一个字形
+    fn num_glyphs(&self) -> u16 {
+        self.chars.len() as u16 + 1
+    }
+
+    /// 每个字符的字形都是 (50,0)-(450,700) 的方块
+    fn glyf_and_loca(&self) -> (Vec<u8>, Vec<u8>) {
+        let mut glyf = Vec::new();
+        let mut loca = Vec::new();
+        push_u16(&mut loca, 0);
+        push_u16(&mut loca, 0); // .notdef 为空字形
+
+        for _ in &self.chars {
+            push_u16(&mut glyf, 1); // numberOfContours
+            for v in [50, 0, 450, 700] {
+                push_u16(&mut glyf, v); // xMin, yMin, xMax, yMax
+            }
+            push_u16(&mut glyf, 3); // endPtsOfContours
+            push_u16(&mut glyf, 0); // instructionLength
+            glyf.extend_from_slice(&[0x01; 4]); // 均为曲线上的点，坐标为 16 位
+            for dx in [50i16, 400, 0, -400] {
+                push_u16(&mut glyf, dx as u16);
+            }
+            for dy in [0i16, 0, 700, 0] {
+                push_u16(&mut glyf, dy as u16);
+            }
+            push_u16(&mut loca, (glyf.len() / 2) as u16);
+        }
+        (glyf, loca)
+    }
+
+    /// cmap 格式 12，每个字符一个分组
+    fn cmap(&self) -> Vec<u8> {
+        let mut t = Vec::new();
+        push_u16(&mut t, 0); // version
+        push_u16(&mut t, 1); // numTables
+        push_u16(&mut t, 3); // platformID: Windows
+        push_u16(&mut t, 10); // encodingID: Unicode full
+        push_u32(&mut t, 12); // subtable offset
+
+        let mut chars: Vec<(u32, u32)> = self
+            .chars
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| (c as u32, i as u32 + 1))
+            .collect();
+        chars.sort();
+        push_u16(&mut t, 12); // format
+        push_u16(&mut t, 0); // reserved
+        push_u32(&mut t, 16 + chars.len() as u32 * 12); // length
+        push_u32(&mut t, 0); // language
+        push_u32(&mut t, chars.len() as u32);
+        for (code, glyph_id) in chars {
+            push_u32(&mut t, code);
+            push_u32(&mut t, code);
+            push_u32(&mut t, glyph_id);
+        }
+        t
+    }
+
+    fn hmtx(&self) -> Vec<u8> {
+        let mut t = Vec::new();
+        push_u16(&mut t, 500);
+        push_u16(&mut t, 0);
+        for _ in &self.chars {
+            push_u16(&mut t, 500); // advanceWidth
+            push_u16(&mut t, 50); // lsb
+        }
+        t
+    }
+
+    /// 版本 1、不含任何 strike 的 sbix 表
+    fn sbix(&self) -> Vec<u8> {
+        let mut t = Vec::new();
+        push_u16(&mut t, 1); // version
+        push_u16(&mut t, 1); // flags
+        push_u32(&mut t, 0); // numStrikes
+        t
+    }
+
+    /// 只包含特性列表的 GSUB 表，脚本列表和查找列表为空，特性不引用任何查找
+    fn gsub(&self) -> Vec<u8> {
+        let feature_list_len = 2 + self.features.len() * (6 + 4);
+        let mut t = Vec::new();
+        push_u32(&mut t, 0x0001_0000); // version 1.0
+        push_u16(&mut t, 10); // scriptListOffset
+        push_u16(&mut t, 12); // featureListOffset
+        push_u16(&mut t, (12 + feature_list_len) as u16); // lookupListOffset
+        push_u16(&mut t, 0); // scriptCount
+
+        push_u16(&mut t, self.features.len() as u16);
+        for (i, tag) in self.features.iter().enumerate() {
+            t.extend_from_slice(tag);
+            push_u16(&mut t, (2 + self.features.len() * 6 + i * 4) as u16);
+        }
+        for _ in &self.features {
+            push_u16(&mut t, 0); // featureParamsOffset
+            push_u16(&mut t, 0); // lookupIndexCount
+        }
+
+        push_u16(&mut t, 0); // lookupCount
+        t
+    }
+
+    fn post(&self) -> Vec<u8> {
+        let mut t = Vec::new();
+        push_u32(&mut t, 0x0002_0000); // version 2.0
+        t.extend_from_slice(&[0; 28]); // italicAngle ... maxMemType1
+        push_u16(&mut t, self.num_glyphs());
+        push_u16(&mut t, 0); // .notdef 使用标准名称
+        for i in 0..self.chars.len() {
+            push_u16(&mut t, 258 + i as u16); // 自定义名称索引
+        }
+        for &c in &self.chars {
+            let name = format!("uni{:04X}", c as u32);
+            t.push(name.len() as u8);
+            t.extend_from_slice(name.as_bytes());
+        }
+        t
+    }
+
+    fn os2(&self) -> Vec<u8> {
+        let mut t = Vec::new();
+        push_u16(&mut t, self.os2_version);
+        push_u16(&mut t, 500); // xAvgCharWidth
+        push_u16(&mut t, self.weight_class);
+        push_u16(&mut t, self.width_class);
+        push_u16(&mut t, self.fs_type);
+        t.extend_from_slice(&[0; 20]); // subscript/superscript/strikeout
+        push_u16(&mut t, 0); // sFamilyClass
+        t.extend_from_slice(&[0; 10]); // panose
+        t.extend_from_slice(&[0; 16]); // ulUnicodeRange1-4
+        t.extend_from_slice(b"TEST"); // achVendID
+        push_u16(&mut t, self.fs_selection);
+        push_u16(&mut t, 0x20); // usFirstCharIndex
+        push_u16(&mut t, 0x7E); // usLastCharIndex
+        push_u16(&mut t, 800); // sTypoAscender
+        push_u16(&mut t, (-200i16) as u16); // sTypoDescender
+        push_u16(&mut t, 0); // sTypoLineGap
+        push_u16(&mut t, 800); // usWinAscent
+        push_u16(&mut t, 200); // usWinDescent
+        if self.os2_version >= 1 {
+            t.extend_from_slice(&[0; 8]); // ulCodePageRange1-2
+        }
+        if self.os2_version >= 2 {
+            t.extend_from_slice(&[0; 10]); // sxHeight ... usMaxContext
+        }
+        t
+    }
+
+    fn name(&self) -> Vec<u8> {
+        let full_name = format!("{} {}", self.family, self.subfamily);
+        let records: [(u16, &str); 6] = [
+            (1, &self.family),
+            (2, &self.subfamily),
+            (3, &self.postscript_name),
+            (4, &full_name),
+            (5, &self.version),
+            (6, &self.postscript_name),
+        ];
+
+        let mut strings = Vec::new();
+        let mut t = Vec::new();
+        push_u16(&mut t, 0); // format
+        push_u16(&mut t, records.len() as u16);
+        push_u16(&mut t, (6 + records.len() * 12) as u16); // stringOffset
+        for (name_id, value) in records {
+            let encoded: Vec<u8> = value.encode_utf16().flat_map(|c| c.to_be_bytes()).collect();
+            push_u16(&mut t, 3); // platformID: Windows
+            push_u16(&mut t, 1); // encodingID: Unicode BMP
+            push_u16(&mut t, 0x0409); // languageID: en-US
+            push_u16(&mut t, name_id);
+            push_u16(&mut t, encoded.len() as u16);
+            push_u16(&mut t, strings.len() as u16);
+            strings.extend_from_slice(&encoded);
+        }
+        t.extend_from_slice(&strings);
+        t
+    }
+}
+
+/// 将各表拼装为 SFNT 文件（表记录按标签排序）
+pub fn build_sfnt(tables: &BTreeMap<[u8; 4], Vec<u8>>) -> Vec<u8> {
+    let mut out = Vec::new();
+    push_u32(&mut out, 0x0001_0000);
+    push_u16(&mut out, tables.len() as u16);
+    out.extend_from_slice(&[0; 6]); // searchRange, entrySelector, rangeShift
+
+    let mut offset = 12 + tables.len() * 16;
+    for (tag, data) in tables {
+        out.extend_from_slice(tag);
+        push_u32(&mut out, 0); // checksum
+        push_u32(&mut out, offset as u32);
+        push_u32(&mut out, data.len() as u32);
+        offset += (data.len() + 3) & !3;
+    }
+
+    for data in tables.values() {
+        out.extend_from_slice(data);
+        out.resize((out.len() + 3) & !3, 0);
+    }
+    out
+}
+
+fn push_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_u32(buf: &mut Vec<u8>, value: u32) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
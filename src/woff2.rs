@@ -0,0 +1,833 @@
+//! WOFF2 解码
+//!
+//! 将 WOFF2 数据还原为普通的 SFNT（TTF/OTF）字节，包括 glyf/loca/hmtx 表的逆变换。
+//! 不支持 WOFF2 字体集合。
+
+use std::io::Read;
+
+/// WOFF2 表目录中预定义的表标签
+const KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+const HEADER_SIZE: usize = 48;
+
+// 组合字形标志位
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+// 简单字形标志位
+const ON_CURVE_POINT: u8 = 0x01;
+const X_SHORT_VECTOR: u8 = 0x02;
+const Y_SHORT_VECTOR: u8 = 0x04;
+const X_IS_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_IS_SAME_OR_POSITIVE: u8 = 0x20;
+const OVERLAP_SIMPLE: u8 = 0x40;
+
+/// 表目录项
+struct TableEntry {
+    tag: [u8; 4],
+    transform_version: u8,
+    orig_length: usize,
+    stream_length: usize,
+}
+
+impl TableEntry {
+    /// glyf/loca 的版本 0 和其他表的非 0 版本表示数据经过变换
+    fn is_transformed(&self) -> bool {
+        match &self.tag {
+            b"glyf" | b"loca" => self.transform_version != 3,
+            _ => self.transform_version != 0,
+        }
+    }
+}
+
+/// 将 WOFF2 数据解码为 SFNT 字节
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut header = Reader::new(data);
+    if header.bytes(4)? != b"wOF2" {
+        return Err("不是 WOFF2 数据".to_string());
+    }
+    let flavor = header.u32()?;
+    if flavor == u32::from_be_bytes(*b"ttcf") {
+        return Err("暂不支持 WOFF2 字体集合".to_string());
+    }
+    header.skip(4)?; // length
+    let num_tables = header.u16()? as usize;
+    header.skip(2)?; // reserved
+    let total_sfnt_size = header.u32()? as u64;
+    let compressed_size = header.u32()? as usize;
+
+    let mut directory = Reader::new(data.get(HEADER_SIZE..).unwrap_or_default());
+    let mut entries = Vec::with_capacity(num_tables);
+    for _ in 0..num_tables {
+        let flags = directory.u8()?;
+        let tag = match flags & 0x3F {
+            63 => directory.tag()?,
+            index => *KNOWN_TAGS[index as usize],
+        };
+        let mut entry = TableEntry {
+            tag,
+            transform_version: flags >> 6,
+            orig_length: directory.base128()? as usize,
+            stream_length: 0,
+        };
+        entry.stream_length = if entry.is_transformed() {
+            directory.base128()? as usize
+        } else {
+            entry.orig_length
+        };
+        entries.push(entry);
+    }
+
+    let compressed_start = HEADER_SIZE + directory.pos;
+    let compressed = data
+        .get(compressed_start..compressed_start + compressed_size)
+        .ok_or_else(|| "WOFF2 压缩数据不完整".to_string())?;
+    // 解压结果不会超过还原后的字体大小，以此为上限防止小文件解压出超大数据
+    let mut stream = Vec::new();
+    brotli_decompressor::Decompressor::new(compressed, 4096)
+        .take(total_sfnt_size + 1)
+        .read_to_end(&mut stream)
+        .map_err(|e| format!("WOFF2 解压失败: {}", e))?;
+    if stream.len() as u64 > total_sfnt_size {
+        return Err("WOFF2 解压数据超过 totalSfntSize".to_string());
+    }
+
+    // 按目录顺序切分解压后的表数据
+    let mut raw_tables = Vec::with_capacity(entries.len());
+    let mut offset = 0;
+    for entry in &entries {
+        let table = stream
+            .get(offset..offset + entry.stream_length)
+            .ok_or_else(|| "WOFF2 表数据越界".to_string())?;
+        raw_tables.push(table);
+        offset += entry.stream_length;
+    }
+
+    let find = |tag: &[u8; 4]| entries.iter().position(|e| &e.tag == tag);
+    let mut tables: Vec<([u8; 4], Vec<u8>)> = Vec::with_capacity(entries.len());
+
+    // glyf 与 loca 需一同重建，hmtx 的逆变换依赖重建后的 xMin
+    let mut x_mins = None;
+    if let Some(glyf_index) = find(b"glyf") {
+        if entries[glyf_index].is_transformed() {
+            let loca_index = find(b"loca").ok_or_else(|| "WOFF2 缺少 loca 表".to_string())?;
+            let glyf = reconstruct_glyf(raw_tables[glyf_index])?;
+            if glyf.loca.len() != entries[loca_index].orig_length {
+                return Err("WOFF2 loca 表长度不匹配".to_string());
+            }
+            tables.push((*b"glyf", glyf.glyf));
+            tables.push((*b"loca", glyf.loca));
+            x_mins = Some(glyf.x_mins);
+        }
+    }
+
+    for (entry, raw) in entries.iter().zip(&raw_tables) {
+        if tables.iter().any(|(tag, _)| tag == &entry.tag) {
+            continue;
+        }
+        let table = if !entry.is_transformed() {
+            raw.to_vec()
+        } else if &entry.tag == b"hmtx" {
+            let x_mins = x_mins
+                .as_deref()
+                .ok_or_else(|| "WOFF2 hmtx 变换需要变换后的 glyf 表".to_string())?;
+            let hhea = find(b"hhea").map(|i| raw_tables[i]).unwrap_or_default();
+            let num_h_metrics = Reader::new(hhea.get(34..).unwrap_or_default()).u16()?;
+            reconstruct_hmtx(raw, x_mins, num_h_metrics as usize)?
+        } else {
+            return Err(format!(
+                "不支持的 WOFF2 表变换: {}",
+                String::from_utf8_lossy(&entry.tag)
+            ));
+        };
+        tables.push((entry.tag, table));
+    }
+
+    build_sfnt(flavor, tables)
+}
+
+/// 重建后的 glyf/loca 数据
+struct GlyfTables {
+    glyf: Vec<u8>,
+    loca: Vec<u8>,
+    x_mins: Vec<i16>,
+}
+
+/// 对变换后的 glyf 表做逆变换，同时生成 loca 表
+fn reconstruct_glyf(data: &[u8]) -> Result<GlyfTables, String> {
+    let mut header = Reader::new(data);
+    header.skip(2)?; // reserved
+    let option_flags = header.u16()?;
+    let num_glyphs = header.u16()? as usize;
+    let index_format = header.u16()?;
+
+    let mut sizes = [0usize; 7];
+    for size in &mut sizes {
+        *size = header.u32()? as usize;
+    }
+    let mut streams = Vec::with_capacity(sizes.len());
+    for size in sizes {
+        streams.push(Reader::new(header.bytes(size)?));
+    }
+    let overlap_bitmap = if option_flags & 1 != 0 {
+        header.bytes(num_glyphs.div_ceil(8))?
+    } else {
+        &[]
+    };
+
+    let mut streams = streams.into_iter();
+    let mut n_contours = streams.next().unwrap();
+    let mut n_points = streams.next().unwrap();
+    let mut flags = streams.next().unwrap();
+    let mut glyphs = streams.next().unwrap();
+    let mut composites = streams.next().unwrap();
+    let mut bboxes = streams.next().unwrap();
+    let mut instructions = streams.next().unwrap();
+
+    let bbox_bitmap = bboxes.bytes(4 * num_glyphs.div_ceil(32))?;
+    let has_bit = |bitmap: &[u8], index: usize| {
+        bitmap
+            .get(index / 8)
+            .is_some_and(|byte| byte & (0x80 >> (index % 8)) != 0)
+    };
+
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::with_capacity(num_glyphs + 1);
+    let mut x_mins = Vec::with_capacity(num_glyphs);
+
+    for glyph_id in 0..num_glyphs {
+        offsets.push(glyf.len());
+        let contour_count = n_contours.u16()? as i16;
+        let explicit_bbox = if has_bit(bbox_bitmap, glyph_id) {
+            Some([bboxes.i16()?, bboxes.i16()?, bboxes.i16()?, bboxes.i16()?])
+        } else {
+            None
+        };
+
+        if contour_count == 0 {
+            if explicit_bbox.is_some() {
+                return Err("WOFF2 空字形不应包含边界框".to_string());
+            }
+            x_mins.push(0);
+            continue;
+        }
+
+        if contour_count < 0 {
+            // 组合字形：组件数据原样拷贝，边界框必须显式给出
+            let bbox = explicit_bbox.ok_or_else(|| "WOFF2 组合字形缺少边界框".to_string())?;
+            let start = composites.pos;
+            let mut have_instructions = false;
+            loop {
+                let component_flags = composites.u16()?;
+                have_instructions |= component_flags & WE_HAVE_INSTRUCTIONS != 0;
+                let mut arg_size = 2;
+                if component_flags & ARG_1_AND_2_ARE_WORDS != 0 {
+                    arg_size += 4;
+                } else {
+                    arg_size += 2;
+                }
+                if component_flags & WE_HAVE_A_SCALE != 0 {
+                    arg_size += 2;
+                } else if component_flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                    arg_size += 4;
+                } else if component_flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                    arg_size += 8;
+                }
+                composites.skip(arg_size)?;
+                if component_flags & MORE_COMPONENTS == 0 {
+                    break;
+                }
+            }
+
+            push_i16(&mut glyf, -1);
+            bbox.iter().for_each(|&v| push_i16(&mut glyf, v));
+            glyf.extend_from_slice(&composites.data[start..composites.pos]);
+            if have_instructions {
+                let length = glyphs.u255()?;
+                push_u16(&mut glyf, length);
+                glyf.extend_from_slice(instructions.bytes(length as usize)?);
+            }
+            x_mins.push(bbox[0]);
+            pad4(&mut glyf);
+            continue;
+        }
+
+        // 简单字形
+        let mut end_points = Vec::with_capacity(contour_count as usize);
+        let mut total_points = 0usize;
+        for _ in 0..contour_count {
+            let contour_points = n_points.u255()? as usize;
+            if contour_points == 0 {
+                return Err("WOFF2 字形轮廓点数为 0".to_string());
+            }
+            total_points += contour_points;
+            end_points.push(total_points - 1);
+        }
+        if total_points > u16::MAX as usize + 1 {
+            return Err("WOFF2 字形点数超出范围".to_string());
+        }
+
+        let point_flags = flags.bytes(total_points)?;
+        let points = decode_triplets(point_flags, &mut glyphs)?;
+        let instruction_length = glyphs.u255()?;
+
+        let bbox = explicit_bbox.unwrap_or_else(|| {
+            let (mut x_min, mut y_min) = (i32::MAX, i32::MAX);
+            let (mut x_max, mut y_max) = (i32::MIN, i32::MIN);
+            for &(x, y, _) in &points {
+                x_min = x_min.min(x);
+                y_min = y_min.min(y);
+                x_max = x_max.max(x);
+                y_max = y_max.max(y);
+            }
+            [x_min as i16, y_min as i16, x_max as i16, y_max as i16]
+        });
+
+        push_i16(&mut glyf, contour_count);
+        bbox.iter().for_each(|&v| push_i16(&mut glyf, v));
+        for end_point in end_points {
+            push_u16(&mut glyf, end_point as u16);
+        }
+        push_u16(&mut glyf, instruction_length);
+        glyf.extend_from_slice(instructions.bytes(instruction_length as usize)?);
+        write_points(&mut glyf, &points, has_bit(overlap_bitmap, glyph_id));
+
+        x_mins.push(bbox[0]);
+        pad4(&mut glyf);
+    }
+    offsets.push(glyf.len());
+
+    let mut loca = Vec::new();
+    for offset in offsets {
+        if index_format == 0 {
+            push_u16(&mut loca, (offset / 2) as u16);
+        } else {
+            loca.extend_from_slice(&(offset as u32).to_be_bytes());
+        }
+    }
+
+    Ok(GlyfTables { glyf, loca, x_mins })
+}
+
+/// 解码三元组编码的点坐标，返回绝对坐标和是否在曲线上
+fn decode_triplets(flags: &[u8], glyphs: &mut Reader) -> Result<Vec<(i32, i32, bool)>, String> {
+    let with_sign = |flag: u8, value: i32| if flag & 1 != 0 { value } else { -value };
+
+    let mut points = Vec::with_capacity(flags.len());
+    let (mut x, mut y) = (0i32, 0i32);
+    for &raw_flag in flags {
+        let on_curve = raw_flag >> 7 == 0;
+        let flag = raw_flag & 0x7F;
+        let (dx, dy) = if flag < 10 {
+            let b = glyphs.u8()? as i32;
+            (0, with_sign(flag, (((flag & 14) as i32) << 7) + b))
+        } else if flag < 20 {
+            let b = glyphs.u8()? as i32;
+            (with_sign(flag, ((((flag - 10) & 14) as i32) << 7) + b), 0)
+        } else if flag < 84 {
+            let b0 = (flag - 20) as i32;
+            let b1 = glyphs.u8()? as i32;
+            (
+                with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+                with_sign(flag >> 1, 1 + ((b0 & 0x0C) << 2) + (b1 & 0x0F)),
+            )
+        } else if flag < 120 {
+            let b0 = (flag - 84) as i32;
+            let (b1, b2) = (glyphs.u8()? as i32, glyphs.u8()? as i32);
+            (
+                with_sign(flag, 1 + ((b0 / 12) << 8) + b1),
+                with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b2),
+            )
+        } else if flag < 124 {
+            let (b1, b2, b3) = (
+                glyphs.u8()? as i32,
+                glyphs.u8()? as i32,
+                glyphs.u8()? as i32,
+            );
+            (
+                with_sign(flag, (b1 << 4) + (b2 >> 4)),
+                with_sign(flag >> 1, ((b2 & 0x0F) << 8) + b3),
+            )
+        } else {
+            let (b1, b2) = (glyphs.u8()? as i32, glyphs.u8()? as i32);
+            let (b3, b4) = (glyphs.u8()? as i32, glyphs.u8()? as i32);
+            (
+                with_sign(flag, (b1 << 8) + b2),
+                with_sign(flag >> 1, (b3 << 8) + b4),
+            )
+        };
+        x += dx;
+        y += dy;
+        points.push((x, y, on_curve));
+    }
+    Ok(points)
+}
+
+/// 以标准 TrueType 编码写出点的标志和坐标（不做重复压缩）
+fn write_points(glyf: &mut Vec<u8>, points: &[(i32, i32, bool)], overlap: bool) {
+    let mut point_flags = Vec::with_capacity(points.len());
+    let mut xs = Vec::new();
+    let mut ys = Vec::new();
+    let (mut last_x, mut last_y) = (0, 0);
+
+    for (index, &(x, y, on_curve)) in points.iter().enumerate() {
+        let mut flag = if on_curve { ON_CURVE_POINT } else { 0 };
+        if overlap && index == 0 {
+            flag |= OVERLAP_SIMPLE;
+        }
+
+        let dx = x - last_x;
+        if dx == 0 {
+            flag |= X_IS_SAME_OR_POSITIVE;
+        } else if dx.abs() < 256 {
+            flag |= X_SHORT_VECTOR;
+            if dx > 0 {
+                flag |= X_IS_SAME_OR_POSITIVE;
+            }
+            xs.push(dx.unsigned_abs() as u8);
+        } else {
+            xs.extend_from_slice(&(dx as i16).to_be_bytes());
+        }
+
+        let dy = y - last_y;
+        if dy == 0 {
+            flag |= Y_IS_SAME_OR_POSITIVE;
+        } else if dy.abs() < 256 {
+            flag |= Y_SHORT_VECTOR;
+            if dy > 0 {
+                flag |= Y_IS_SAME_OR_POSITIVE;
+            }
+            ys.push(dy.unsigned_abs() as u8);
+        } else {
+            ys.extend_from_slice(&(dy as i16).to_be_bytes());
+        }
+
+        point_flags.push(flag);
+        (last_x, last_y) = (x, y);
+    }
+
+    glyf.extend_from_slice(&point_flags);
+    glyf.extend_from_slice(&xs);
+    glyf.extend_from_slice(&ys);
+}
+
+/// 对变换后的 hmtx 表做逆变换，缺失的左侧空白取自字形的 xMin
+fn reconstruct_hmtx(data: &[u8], x_mins: &[i16], num_h_metrics: usize) -> Result<Vec<u8>, String> {
+    let num_glyphs = x_mins.len();
+    if num_h_metrics == 0 || num_h_metrics > num_glyphs {
+        return Err("WOFF2 hmtx 度量数量无效".to_string());
+    }
+
+    let mut reader = Reader::new(data);
+    let flags = reader.u8()?;
+    let mut advances = Vec::with_capacity(num_h_metrics);
+    for _ in 0..num_h_metrics {
+        advances.push(reader.u16()?);
+    }
+
+    let mut lsbs = Vec::with_capacity(num_glyphs);
+    for (glyph_id, &x_min) in x_mins.iter().enumerate() {
+        let present = if glyph_id < num_h_metrics {
+            flags & 1 == 0
+        } else {
+            flags & 2 == 0
+        };
+        lsbs.push(if present { reader.i16()? } else { x_min });
+    }
+
+    let mut hmtx = Vec::with_capacity(num_h_metrics * 4 + (num_glyphs - num_h_metrics) * 2);
+    for (glyph_id, lsb) in lsbs.into_iter().enumerate() {
+        if glyph_id < num_h_metrics {
+            push_u16(&mut hmtx, advances[glyph_id]);
+        }
+        push_i16(&mut hmtx, lsb);
+    }
+    Ok(hmtx)
+}
+
+/// 将各表组装为 SFNT 文件，并计算校验和
+fn build_sfnt(flavor: u32, mut tables: Vec<([u8; 4], Vec<u8>)>) -> Result<Vec<u8>, String> {
+    tables.sort_by_key(|(tag, _)| *tag);
+
+    // 校验和调整值需在计算前清零
+    if let Some((_, head)) = tables.iter_mut().find(|(tag, _)| tag == b"head") {
+        if head.len() >= 12 {
+            head[8..12].fill(0);
+        }
+    }
+
+    // 表目录各字段均为 u16，searchRange 等需在 u32 中计算后检查范围
+    let num_tables = tables.len() as u32;
+    let entry_selector = num_tables.max(1).ilog2();
+    let search_range = (1u32 << entry_selector) * 16;
+    let range_shift = num_tables * 16 - search_range;
+    let to_u16 = |value: u32| u16::try_from(value).map_err(|_| "WOFF2 表数量过多".to_string());
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&flavor.to_be_bytes());
+    push_u16(&mut out, to_u16(num_tables)?);
+    push_u16(&mut out, to_u16(search_range)?);
+    push_u16(&mut out, to_u16(entry_selector)?);
+    push_u16(&mut out, to_u16(range_shift)?);
+
+    let mut offset = 12 + tables.len() * 16;
+    for (tag, data) in &tables {
+        out.extend_from_slice(tag);
+        out.extend_from_slice(&checksum(data).to_be_bytes());
+        out.extend_from_slice(&(offset as u32).to_be_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        offset += data.len().div_ceil(4) * 4;
+    }
+
+    let mut head_offset = None;
+    for (tag, data) in &tables {
+        if tag == b"head" {
+            head_offset = Some(out.len());
+        }
+        out.extend_from_slice(data);
+        pad4(&mut out);
+    }
+
+    if let Some(head_offset) = head_offset {
+        if out.len() >= head_offset + 12 {
+            let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&out));
+            out[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+        }
+    }
+    Ok(out)
+}
+
+/// 计算 SFNT 表校验和
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn push_u16(buf: &mut Vec<u8>, value: u16) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_i16(buf: &mut Vec<u8>, value: i16) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn pad4(buf: &mut Vec<u8>) {
+    buf.resize(buf.len().div_ceil(4) * 4, 0);
+}
+
+/// 大端字节读取器
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| "WOFF2 数据意外结束".to_string())?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        self.u16().map(|v| v as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn tag(&mut self) -> Result<[u8; 4], String> {
+        let bytes = self.bytes(4)?;
+        Ok([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    /// UIntBase128 变长整数
+    fn base128(&mut self) -> Result<u32, String> {
+        let mut value: u32 = 0;
+        for index in 0..5 {
+            let byte = self.u8()?;
+            if index == 0 && byte == 0x80 {
+                return Err("WOFF2 UIntBase128 含有前导零".to_string());
+            }
+            if value & 0xFE00_0000 != 0 {
+                return Err("WOFF2 UIntBase128 溢出".to_string());
+            }
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("WOFF2 UIntBase128 过长".to_string())
+    }
+
+    /// 255UInt16 变长整数
+    fn u255(&mut self) -> Result<u16, String> {
+        match self.u8()? {
+            253 => self.u16(),
+            254 => Ok(self.u8()? as u16 + 253 * 2),
+            255 => Ok(self.u8()? as u16 + 253),
+            code => Ok(code as u16),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::test_fonts::TestFont;
+    use std::io::Write;
+
+    /// 测试用的 WOFF2 编码：表数据按给定的变换版本原样写入
+    fn encode(tables: &[([u8; 4], u8, Vec<u8>, usize)]) -> Vec<u8> {
+        let mut directory = Vec::new();
+        let mut stream = Vec::new();
+        for (tag, version, data, orig_length) in tables {
+            match KNOWN_TAGS.iter().position(|known| *known == tag) {
+                Some(index) => directory.push(index as u8 | (version << 6)),
+                None => {
+                    directory.push(63 | (version << 6));
+                    directory.extend_from_slice(tag);
+                }
+            }
+            push_base128(&mut directory, *orig_length as u32);
+            let transformed = match tag {
+                b"glyf" | b"loca" => *version != 3,
+                _ => *version != 0,
+            };
+            if transformed {
+                push_base128(&mut directory, data.len() as u32);
+            }
+            stream.extend_from_slice(data);
+        }
+
+        let mut compressed = Vec::new();
+        {
+            let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 9, 22);
+            writer.write_all(&stream).unwrap();
+        }
+
+        let mut out = Vec::new();
+        out.extend_from_slice(b"wOF2");
+        out.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+        out.extend_from_slice(&0u32.to_be_bytes()); // length
+        push_u16(&mut out, tables.len() as u16);
+        push_u16(&mut out, 0);
+        let total_sfnt_size = 12
+            + 16 * tables.len()
+            + tables
+                .iter()
+                .map(|(_, _, _, orig_length)| orig_length.div_ceil(4) * 4)
+                .sum::<usize>();
+        out.extend_from_slice(&(total_sfnt_size as u32).to_be_bytes());
+        out.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+        out.extend_from_slice(&[0; 24]); // version, metadata, private data
+        out.extend_from_slice(&directory);
+        out.extend_from_slice(&compressed);
+        let length = out.len() as u32;
+        out[8..12].copy_from_slice(&length.to_be_bytes());
+        out
+    }
+
+    /// 测试用的 WOFF2 编码：所有表均不做变换（glyf/loca 使用空变换）
+    pub(crate) fn encode_sfnt(sfnt: &[u8]) -> Vec<u8> {
+        let tables: Vec<_> = tables_of(sfnt)
+            .into_iter()
+            .map(|(tag, data)| {
+                let length = data.len();
+                // glyf/loca 的空变换版本为 3
+                let version = if matches!(&tag, b"glyf" | b"loca") {
+                    3
+                } else {
+                    0
+                };
+                (tag, version, data, length)
+            })
+            .collect();
+        encode(&tables)
+    }
+
+    fn push_base128(buf: &mut Vec<u8>, value: u32) {
+        let mut bytes = vec![(value & 0x7F) as u8];
+        let mut rest = value >> 7;
+        while rest != 0 {
+            bytes.push((rest & 0x7F) as u8 | 0x80);
+            rest >>= 7;
+        }
+        buf.extend(bytes.into_iter().rev());
+    }
+
+    fn tables_of(sfnt: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        let face = ttf_parser::RawFace::parse(sfnt, 0).unwrap();
+        face.table_records
+            .into_iter()
+            .map(|record| {
+                let start = record.offset as usize;
+                let data = sfnt[start..start + record.length as usize].to_vec();
+                (record.tag.to_bytes(), data)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip_null_transform() {
+        let original = TestFont::new("Demo").build();
+        let decoded = decode(&encode_sfnt(&original)).unwrap();
+        for ((tag, expected), (decoded_tag, actual)) in
+            tables_of(&original).into_iter().zip(tables_of(&decoded))
+        {
+            assert_eq!(tag, decoded_tag);
+            if &tag == b"head" {
+                // 校验和调整值会被重新计算
+                assert_eq!(expected[..8], actual[..8]);
+                assert_eq!(expected[12..], actual[12..]);
+            } else {
+                assert_eq!(expected, actual);
+            }
+        }
+
+        let face = ttf_parser::Face::parse(&decoded, 0).unwrap();
+        assert_eq!(face.number_of_glyphs(), 1);
+    }
+
+    #[test]
+    fn test_reconstruct_transformed_glyf() {
+        // 两个字形：0 为空字形，1 为三角形 (0,0) (100,0) (50,100)
+        let streams: [&[u8]; 7] = [
+            &[0, 0, 0, 1],        // nContour
+            &[3],                 // nPoints
+            &[0, 11, 86],         // flags
+            &[0, 100, 49, 99, 0], // 坐标三元组 + 指令长度
+            &[],                  // composite
+            &[0, 0, 0, 0],        // bbox 位图
+            &[],                  // instructions
+        ];
+        let mut glyf = Vec::new();
+        push_u16(&mut glyf, 0);
+        push_u16(&mut glyf, 0);
+        push_u16(&mut glyf, 2); // numGlyphs
+        push_u16(&mut glyf, 0); // indexFormat
+        for stream in streams {
+            glyf.extend_from_slice(&(stream.len() as u32).to_be_bytes());
+        }
+        for stream in streams {
+            glyf.extend_from_slice(stream);
+        }
+
+        let mut tables: Vec<_> = tables_of(&TestFont::new("Demo").build())
+            .into_iter()
+            .map(|(tag, mut data)| {
+                if &tag == b"maxp" {
+                    data[4..6].copy_from_slice(&2u16.to_be_bytes());
+                }
+                let length = data.len();
+                (tag, 0, data, length)
+            })
+            .collect();
+        tables.push((*b"glyf", 0, glyf, 0));
+        tables.push((*b"loca", 0, Vec::new(), 6));
+
+        let decoded = decode(&encode(&tables)).unwrap();
+        let face = ttf_parser::Face::parse(&decoded, 0).unwrap();
+        assert_eq!(face.number_of_glyphs(), 2);
+        assert_eq!(
+            face.glyph_bounding_box(ttf_parser::GlyphId(1)),
+            Some(ttf_parser::Rect {
+                x_min: 0,
+                y_min: 0,
+                x_max: 100,
+                y_max: 100
+            })
+        );
+        assert_eq!(face.glyph_bounding_box(ttf_parser::GlyphId(0)), None);
+    }
+
+    #[test]
+    fn test_rejects_zero_point_contour() {
+        let streams: [&[u8]; 7] = [
+            &[0, 1],       // nContour
+            &[0],          // nPoints
+            &[],           // flags
+            &[0],          // 指令长度
+            &[],           // composite
+            &[0, 0, 0, 0], // bbox 位图
+            &[],           // instructions
+        ];
+        let mut glyf = Vec::new();
+        push_u16(&mut glyf, 0);
+        push_u16(&mut glyf, 0);
+        push_u16(&mut glyf, 1); // numGlyphs
+        push_u16(&mut glyf, 0); // indexFormat
+        for stream in streams {
+            glyf.extend_from_slice(&(stream.len() as u32).to_be_bytes());
+        }
+        for stream in streams {
+            glyf.extend_from_slice(stream);
+        }
+        let Err(error) = reconstruct_glyf(&glyf) else {
+            panic!("点数为 0 的轮廓应被拒绝");
+        };
+        assert!(error.contains("点数为 0"));
+    }
+
+    #[test]
+    fn test_rejects_oversized_stream() {
+        let mut data = encode_sfnt(&TestFont::new("Demo").build());
+        // 将 totalSfntSize 改小，解压数据超出上限
+        data[16..20].copy_from_slice(&16u32.to_be_bytes());
+        assert!(decode(&data).unwrap_err().contains("totalSfntSize"));
+    }
+
+    #[test]
+    fn test_build_sfnt_rejects_too_many_tables() {
+        let tables = (0..4096u32)
+            .map(|i| (i.to_be_bytes(), Vec::new()))
+            .collect();
+        assert!(build_sfnt(0x0001_0000, tables).is_err());
+        let tables = (0..4095u32)
+            .map(|i| (i.to_be_bytes(), Vec::new()))
+            .collect();
+        assert!(build_sfnt(0x0001_0000, tables).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_non_woff2() {
+        assert!(decode(b"wOFFxxxx").is_err());
+        assert!(decode(b"").is_err());
+    }
+}